@@ -0,0 +1,657 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env,
+    fs::File,
+    io::{Read, Write},
+    path::Path,
+};
+
+const REGISTRY: &str = "registry/gl.xml";
+
+const VERSIONS: &[(&str, &str, &str)] = &[
+    ("gl21", "gl", "2.1"),
+    ("gl33", "gl", "3.3"),
+    ("gl45", "gl", "4.5"),
+    ("gl46", "gl", "4.6"),
+    ("gles20", "gles2", "2.0"),
+    ("gles30", "gles2", "3.0"),
+    ("gles32", "gles2", "3.2"),
+];
+
+// Every driver the crate supports has these, anything outside of them is loaded as optional.
+const REQUIRED: &[(&str, &str)] = &[("gl", "2.1"), ("gles2", "2.0")];
+
+const EXTENSIONS: &[&str] = &[
+    "GL_ARB_ES2_compatibility",
+    "GL_ARB_debug_output",
+    "GL_ARB_draw_instanced",
+    "GL_ARB_framebuffer_object",
+    "GL_ARB_framebuffer_sRGB",
+    "GL_ARB_instanced_arrays",
+    "GL_ARB_pixel_buffer_object",
+    "GL_ARB_robustness",
+    "GL_ARB_sync",
+    "GL_ARB_texture_filter_anisotropic",
+    "GL_ARB_timer_query",
+    "GL_ARB_vertex_array_object",
+    "GL_EXT_color_buffer_half_float",
+    "GL_EXT_disjoint_timer_query",
+    "GL_EXT_draw_instanced",
+    "GL_EXT_framebuffer_object",
+    "GL_EXT_framebuffer_sRGB",
+    "GL_EXT_instanced_arrays",
+    "GL_EXT_map_buffer_range",
+    "GL_EXT_multisampled_render_to_texture",
+    "GL_EXT_robustness",
+    "GL_EXT_sRGB",
+    "GL_EXT_texture_filter_anisotropic",
+    "GL_KHR_debug",
+    "GL_KHR_no_error",
+    "GL_KHR_robustness",
+    "GL_NV_pixel_buffer_object",
+    "GL_OES_depth24",
+    "GL_OES_element_index_uint",
+    "GL_OES_mapbuffer",
+    "GL_OES_packed_depth_stencil",
+    "GL_OES_rgb8_rgba8",
+    "GL_OES_texture_half_float",
+    "GL_OES_vertex_array_object",
+];
+
+// The crate's own safe layers use these, they are emitted as optional for every selection.
+const CRATE_COMMANDS: &[&str] = &[
+    "glBindVertexArray",
+    "glClientWaitSync",
+    "glDebugMessageCallback",
+    "glDebugMessageControl",
+    "glDebugMessageInsert",
+    "glDeleteSync",
+    "glDeleteVertexArrays",
+    "glDrawBuffers",
+    "glFenceSync",
+    "glGenVertexArrays",
+    "glGetActiveUniformBlockName",
+    "glGetActiveUniformBlockiv",
+    "glGetActiveUniformsiv",
+    "glGetFramebufferAttachmentParameteriv",
+    "glGetStringi",
+    "glMapBufferRange",
+    "glObjectLabel",
+    "glReadBuffer",
+    "glUnmapBuffer",
+];
+
+// Enums the crate's own safe layers use, they are emitted for every selection.
+const CRATE_ENUMS: &[&str] = &[
+    "GL_ACTIVE_UNIFORM_BLOCKS",
+    "GL_ACTIVE_UNIFORM_BLOCK_MAX_NAME_LENGTH",
+    "GL_ALPHA_BITS",
+    "GL_ALREADY_SIGNALED",
+    "GL_BACK_LEFT",
+    "GL_BGR",
+    "GL_BGRA",
+    "GL_BLUE",
+    "GL_BLUE_BITS",
+    "GL_COLOR_ATTACHMENT0",
+    "GL_CONDITION_SATISFIED",
+    "GL_CONTEXT_CORE_PROFILE_BIT",
+    "GL_CONTEXT_PROFILE_MASK",
+    "GL_DEPTH",
+    "GL_DEPTH_ATTACHMENT",
+    "GL_DEPTH_BITS",
+    "GL_DEPTH_STENCIL",
+    "GL_DEPTH_STENCIL_ATTACHMENT",
+    "GL_DOUBLE",
+    "GL_DRAW_BUFFER",
+    "GL_FLOAT_32_UNSIGNED_INT_24_8_REV",
+    "GL_FRAMEBUFFER",
+    "GL_FRAMEBUFFER_ATTACHMENT_ALPHA_SIZE",
+    "GL_FRAMEBUFFER_ATTACHMENT_BLUE_SIZE",
+    "GL_FRAMEBUFFER_ATTACHMENT_COLOR_ENCODING",
+    "GL_FRAMEBUFFER_ATTACHMENT_DEPTH_SIZE",
+    "GL_FRAMEBUFFER_ATTACHMENT_GREEN_SIZE",
+    "GL_FRAMEBUFFER_ATTACHMENT_OBJECT_TYPE",
+    "GL_FRAMEBUFFER_ATTACHMENT_RED_SIZE",
+    "GL_FRAMEBUFFER_ATTACHMENT_STENCIL_SIZE",
+    "GL_FRAMEBUFFER_BINDING",
+    "GL_FRAMEBUFFER_COMPLETE",
+    "GL_FRAMEBUFFER_INCOMPLETE_ATTACHMENT",
+    "GL_FRAMEBUFFER_INCOMPLETE_DRAW_BUFFER",
+    "GL_FRAMEBUFFER_INCOMPLETE_LAYER_TARGETS",
+    "GL_FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT",
+    "GL_FRAMEBUFFER_INCOMPLETE_MULTISAMPLE",
+    "GL_FRAMEBUFFER_INCOMPLETE_READ_BUFFER",
+    "GL_FRAMEBUFFER_UNDEFINED",
+    "GL_FRAMEBUFFER_UNSUPPORTED",
+    "GL_FRONT",
+    "GL_FRONT_LEFT",
+    "GL_GREEN",
+    "GL_GREEN_BITS",
+    "GL_HALF_FLOAT",
+    "GL_HALF_FLOAT_OES",
+    "GL_IMPLEMENTATION_COLOR_READ_FORMAT",
+    "GL_IMPLEMENTATION_COLOR_READ_TYPE",
+    "GL_INT_2_10_10_10_REV",
+    "GL_LUMINANCE",
+    "GL_LUMINANCE_ALPHA",
+    "GL_MAP_READ_BIT",
+    "GL_NUM_EXTENSIONS",
+    "GL_PIXEL_PACK_BUFFER",
+    "GL_PIXEL_PACK_BUFFER_BINDING",
+    "GL_PIXEL_UNPACK_BUFFER",
+    "GL_RED",
+    "GL_RED_BITS",
+    "GL_RED_INTEGER",
+    "GL_RENDERBUFFER",
+    "GL_RENDERBUFFER_BINDING",
+    "GL_RG",
+    "GL_RGBA_INTEGER",
+    "GL_RGB_INTEGER",
+    "GL_RG_INTEGER",
+    "GL_SRGB",
+    "GL_STATIC_DRAW",
+    "GL_STENCIL",
+    "GL_STENCIL_ATTACHMENT",
+    "GL_STENCIL_BITS",
+    "GL_STENCIL_INDEX",
+    "GL_SYNC_FLUSH_COMMANDS_BIT",
+    "GL_SYNC_GPU_COMMANDS_COMPLETE",
+    "GL_TIMEOUT_IGNORED",
+    "GL_TRIANGLES",
+    "GL_UNIFORM_ARRAY_STRIDE",
+    "GL_UNIFORM_BLOCK_ACTIVE_UNIFORMS",
+    "GL_UNIFORM_BLOCK_ACTIVE_UNIFORM_INDICES",
+    "GL_UNIFORM_BLOCK_BINDING",
+    "GL_UNIFORM_BLOCK_DATA_SIZE",
+    "GL_UNIFORM_BLOCK_INDEX",
+    "GL_UNIFORM_MATRIX_STRIDE",
+    "GL_UNIFORM_OFFSET",
+    "GL_UNSIGNED_INT_10F_11F_11F_REV",
+    "GL_UNSIGNED_INT_24_8",
+    "GL_UNSIGNED_INT_2_10_10_10_REV",
+    "GL_UNSIGNED_INT_5_9_9_9_REV",
+    "GL_WAIT_FAILED",
+];
+
+// Names kept from the hand-written loader.
+const RENAMES: &[(&str, &str)] = &[
+    ("glFramebufferTexture2D", "framebuffer_texture2d"),
+    ("glGetIntegerv", "get_integer_v"),
+    ("glGetProgramiv", "get_program_iv"),
+    ("glGetShaderiv", "get_shader_iv"),
+    ("glTexParameterf", "tex_parameter_f"),
+    ("glTexParameterfv", "tex_parameter_fv"),
+    ("glTexParameteri", "tex_parameter_i"),
+    ("glTexParameteriv", "tex_parameter_iv"),
+];
+
+const KEYWORDS: &[&str] = &[
+    "as", "box", "break", "const", "continue", "crate", "else", "enum", "extern", "false", "fn",
+    "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref",
+    "return", "self", "static", "struct", "super", "trait", "true", "type", "unsafe", "use",
+    "where", "while",
+];
+
+enum Node {
+    Element(Element),
+    Text(String),
+}
+
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Node>,
+}
+
+impl Element {
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|a| a.0 == name)
+            .map(|a| a.1.as_str())
+    }
+
+    fn elements<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> + 'a {
+        self.children.iter().filter_map(move |c| match c {
+            Node::Element(e) if e.name == name => Some(e),
+            _ => None,
+        })
+    }
+
+    fn text(&self) -> String {
+        let mut s = String::new();
+        for c in &self.children {
+            match c {
+                Node::Element(e) => s.push_str(&e.text()),
+                Node::Text(t) => s.push_str(t),
+            }
+        }
+        s
+    }
+
+    fn text_without(&self, name: &str) -> String {
+        let mut s = String::new();
+        for c in &self.children {
+            match c {
+                Node::Element(e) if e.name != name => s.push_str(&e.text()),
+                Node::Element(_) => (),
+                Node::Text(t) => s.push_str(t),
+            }
+        }
+        s
+    }
+}
+
+fn unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn parse_xml(src: &str) -> Element {
+    let mut stack = vec![Element {
+        name: String::new(),
+        attributes: Vec::new(),
+        children: Vec::new(),
+    }];
+    let mut rest = src;
+    while !rest.is_empty() {
+        if rest.starts_with("<?") {
+            rest = &rest[rest.find("?>").expect("Unterminated declaration") + 2..];
+        } else if rest.starts_with("<!--") {
+            rest = &rest[rest.find("-->").expect("Unterminated comment") + 3..];
+        } else if rest.starts_with("</") {
+            let end = rest.find('>').expect("Unterminated tag");
+            let e = stack.pop().expect("Unbalanced tag");
+            assert_eq!(e.name, rest[2..end].trim(), "Mismatched tag");
+            stack.last_mut().unwrap().children.push(Node::Element(e));
+            rest = &rest[end + 1..];
+        } else if rest.starts_with('<') {
+            let end = rest.find('>').expect("Unterminated tag");
+            let closed = rest[..end].ends_with('/');
+            let tag = rest[1..if closed { end - 1 } else { end }].trim();
+            let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+            let mut attributes = Vec::new();
+            let mut attrs = &tag[name_end..];
+            while let Some(eq) = attrs.find('=') {
+                let key = attrs[..eq].trim().to_string();
+                let open = attrs[eq..].find('"').expect("Unquoted attribute") + eq + 1;
+                let close = attrs[open..].find('"').expect("Unterminated attribute") + open;
+                attributes.push((key, unescape(&attrs[open..close])));
+                attrs = &attrs[close + 1..];
+            }
+            let e = Element {
+                name: tag[..name_end].to_string(),
+                attributes,
+                children: Vec::new(),
+            };
+            if closed {
+                stack.last_mut().unwrap().children.push(Node::Element(e));
+            } else {
+                stack.push(e);
+            }
+            rest = &rest[end + 1..];
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            stack
+                .last_mut()
+                .unwrap()
+                .children
+                .push(Node::Text(unescape(&rest[..end])));
+            rest = &rest[end..];
+        }
+    }
+    assert_eq!(stack.len(), 1, "Unclosed tag");
+    stack.pop().unwrap()
+}
+
+fn rust_base_type(c: &str) -> &'static str {
+    match c {
+        "void" => "c_void",
+        "GLboolean" => "Boolean",
+        "GLbitfield" => "BitField",
+        "GLbyte" => "SByte",
+        "GLchar" => "c_char",
+        "GLclampd" | "GLdouble" => "f64",
+        "GLclampf" | "GLfloat" => "f32",
+        "GLDEBUGPROC" | "GLDEBUGPROCARB" | "GLDEBUGPROCKHR" => "DebugProc",
+        "GLenum" => "Enumerated",
+        "GLfixed" => "Fixed",
+        "GLhalf" => "Half",
+        "GLint" => "SInt",
+        "GLint64" => "SInt64",
+        "GLintptr" => "IntPtr",
+        "GLshort" => "SShort",
+        "GLsizei" => "SizeI",
+        "GLsizeiptr" => "SizeIPtr",
+        "GLsync" => "SyncObject",
+        "GLubyte" => "UByte",
+        "GLuint" => "UInt",
+        "GLuint64" => "UInt64",
+        "GLushort" => "UShort",
+        _ => panic!("Unknown GL type {}", c),
+    }
+}
+
+fn rust_type(c: &str) -> Option<String> {
+    let c = c.replace('*', " * ");
+    let mut base = None;
+    let mut base_const = false;
+    let mut pointers: Vec<bool> = Vec::new();
+    for token in c.split_whitespace() {
+        match token {
+            "const" => match pointers.last_mut() {
+                Some(p) => *p = true,
+                None => base_const = true,
+            },
+            "*" => pointers.push(false),
+            "struct" => (),
+            t => base = Some(t.to_string()),
+        }
+    }
+    let base = base.expect("Missing type");
+    if base == "void" && pointers.is_empty() {
+        return None;
+    }
+    let mut t = rust_base_type(&base).to_string();
+    let mut pointee_const = base_const;
+    for p in pointers {
+        t = format!("*{} {}", if pointee_const { "const" } else { "mut" }, t);
+        pointee_const = p;
+    }
+    Some(t)
+}
+
+fn snake_case(s: &str) -> String {
+    let chars: Vec<char> = s.chars().collect();
+    let mut r = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if i > 0 {
+            let p = chars[i - 1];
+            let next_lower = matches!(chars.get(i + 1), Some(n) if n.is_lowercase());
+            if (c.is_uppercase() && (p.is_lowercase() || (p.is_uppercase() && next_lower)))
+                || (c.is_numeric() && p.is_alphabetic())
+            {
+                r.push('_');
+            }
+        }
+        r.extend(c.to_lowercase());
+    }
+    r
+}
+
+fn function_name(command: &str) -> String {
+    if let Some(r) = RENAMES.iter().find(|r| r.0 == command) {
+        return r.1.to_string();
+    }
+    snake_case(&command[2..])
+}
+
+fn parameter_name(name: &str) -> String {
+    let n = snake_case(name);
+    if KEYWORDS.contains(&n.as_str()) {
+        n + "_"
+    } else {
+        n
+    }
+}
+
+struct Command {
+    parameters: Vec<(String, String)>,
+    result: Option<String>,
+}
+
+fn version_le(a: &str, b: &str) -> bool {
+    let v = |s: &str| -> Vec<u32> { s.split('.').map(|n| n.parse().unwrap()).collect() };
+    v(a) <= v(b)
+}
+
+fn collect(
+    registry: &Element,
+    versions: &[(&str, &str)],
+    extensions: &[&str],
+    commands: &mut BTreeSet<String>,
+    enums: &mut BTreeSet<String>,
+) {
+    let mut require = |r: &Element| {
+        for c in r.elements("command") {
+            commands.insert(c.attribute("name").unwrap().to_string());
+        }
+        for e in r.elements("enum") {
+            enums.insert(e.attribute("name").unwrap().to_string());
+        }
+    };
+    for f in registry.elements("feature") {
+        let api = f.attribute("api").unwrap();
+        let number = f.attribute("number").unwrap();
+        if versions
+            .iter()
+            .any(|v| v.0 == api && version_le(number, v.1))
+        {
+            for r in f.elements("require") {
+                require(r);
+            }
+        }
+    }
+    for es in registry.elements("extensions") {
+        for e in es.elements("extension") {
+            if extensions.contains(&e.attribute("name").unwrap()) {
+                for r in extension_requires(e, versions) {
+                    require(r);
+                }
+            }
+        }
+    }
+}
+
+fn extension_requires<'a>(
+    extension: &'a Element,
+    versions: &'a [(&'a str, &'a str)],
+) -> impl Iterator<Item = &'a Element> + 'a {
+    let supported: Vec<&str> = extension
+        .attribute("supported")
+        .unwrap()
+        .split('|')
+        .collect();
+    extension.elements("require").filter(move |r| {
+        let api_matches = |api: &str| versions.iter().any(|v| v.0 == api);
+        match r.attribute("api") {
+            Some(api) => api_matches(api),
+            None => supported.iter().any(|&api| api_matches(api)),
+        }
+    })
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed={}", REGISTRY);
+
+    let mut versions: Vec<(&str, &str)> = VERSIONS
+        .iter()
+        .filter(|v| env::var_os(format!("CARGO_FEATURE_{}", v.0.to_uppercase())).is_some())
+        .map(|v| (v.1, v.2))
+        .collect();
+    if versions.is_empty() {
+        if env::var("CARGO_CFG_TARGET_OS").unwrap() == "android" {
+            versions.push(("gles2", "3.2"));
+        } else {
+            versions.push(("gl", "3.3"));
+        }
+    }
+
+    let mut src = String::new();
+    File::open(REGISTRY)
+        .expect("Can not open the GL registry")
+        .read_to_string(&mut src)
+        .unwrap();
+    let root = parse_xml(&src);
+    let registry = root.elements("registry").next().expect("Missing registry");
+
+    let mut all_commands = BTreeMap::new();
+    for cs in registry.elements("commands") {
+        for c in cs.elements("command") {
+            let proto = c.elements("proto").next().unwrap();
+            let name = proto.elements("name").next().unwrap().text();
+            let parameters = c
+                .elements("param")
+                .map(|p| {
+                    (
+                        parameter_name(&p.elements("name").next().unwrap().text()),
+                        rust_type(&p.text_without("name")).expect("Void parameter"),
+                    )
+                })
+                .collect();
+            let result = rust_type(&proto.text_without("name"));
+            all_commands.insert(name, Command { parameters, result });
+        }
+    }
+    let mut all_enums = BTreeMap::new();
+    for es in registry.elements("enums") {
+        for e in es.elements("enum") {
+            let t = match e.attribute("type") {
+                Some("ull") => "u64",
+                _ => "u32",
+            };
+            all_enums.insert(
+                e.attribute("name").unwrap().to_string(),
+                (e.attribute("value").unwrap().to_string(), t),
+            );
+        }
+    }
+
+    let mut commands = BTreeSet::new();
+    let mut enums = BTreeSet::new();
+    collect(registry, &versions, EXTENSIONS, &mut commands, &mut enums);
+    commands.extend(CRATE_COMMANDS.iter().map(|c| c.to_string()));
+    enums.extend(CRATE_ENUMS.iter().map(|e| e.to_string()));
+    let mut required = BTreeSet::new();
+    for (i, r) in REQUIRED.iter().enumerate() {
+        let mut cs = BTreeSet::new();
+        collect(registry, &[*r], &[], &mut cs, &mut BTreeSet::new());
+        required = if i == 0 {
+            cs
+        } else {
+            required.intersection(&cs).cloned().collect()
+        };
+    }
+
+    // Commands that only come from extensions are loaded when one of those extensions is present.
+    let mut core = BTreeSet::new();
+    collect(registry, &versions, &[], &mut core, &mut BTreeSet::new());
+    // They are also loaded when the context has a core version that includes them.
+    let mut introduced: BTreeMap<&str, Vec<(&str, &str)>> = BTreeMap::new();
+    for f in registry.elements("feature") {
+        let api = f.attribute("api").unwrap();
+        if !VERSIONS.iter().any(|v| v.1 == api) {
+            continue;
+        }
+        let number = f.attribute("number").unwrap();
+        for r in f.elements("require") {
+            for c in r.elements("command") {
+                let apis = introduced.entry(c.attribute("name").unwrap()).or_default();
+                if !apis.iter().any(|v| v.0 == api) {
+                    apis.push((api, number));
+                }
+            }
+        }
+    }
+    let mut gates: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for es in registry.elements("extensions") {
+        for e in es.elements("extension") {
+            let extension = e.attribute("name").unwrap();
+            if !EXTENSIONS.contains(&extension) {
+                continue;
+            }
+            for r in extension_requires(e, &versions) {
+                for c in r.elements("command") {
+                    let c = c.attribute("name").unwrap();
+                    if !core.contains(c) {
+                        gates.entry(c).or_default().push(extension);
+                    }
+                }
+            }
+        }
+    }
+
+    let selection = versions
+        .iter()
+        .map(|v| format!("{} {}", v.0, v.1))
+        .collect::<Vec<String>>()
+        .join(", ");
+    let out = env::var("OUT_DIR").unwrap();
+
+    let mut entries: BTreeMap<bool, BTreeMap<String, String>> = BTreeMap::new();
+    for name in &commands {
+        let c = all_commands
+            .get(name)
+            .unwrap_or_else(|| panic!("Command {} is not in the registry", name));
+        let parameters = c
+            .parameters
+            .iter()
+            .map(|p| format!("{}: {}", p.0, p.1))
+            .collect::<Vec<String>>()
+            .join(", ");
+        let result = c
+            .result
+            .as_ref()
+            .map_or(String::new(), |r| format!(" -> {}", r));
+        let gate = if required.contains(name) {
+            String::new()
+        } else {
+            let extensions = gates
+                .get(name.as_str())
+                .into_iter()
+                .flatten()
+                .map(|e| format!("\"{}\"", e))
+                .collect::<Vec<String>>()
+                .join(", ");
+            let versions = introduced
+                .get(name.as_str())
+                .into_iter()
+                .flatten()
+                .map(|v| {
+                    let api = if v.0 == "gl" { "Api::Gl" } else { "Api::Gles" };
+                    format!("({}, ({}))", api, v.1.replace('.', ", "))
+                })
+                .collect::<Vec<String>>()
+                .join(", ");
+            format!(" [{}], [{}],", extensions, versions)
+        };
+        entries.entry(required.contains(name)).or_default().insert(
+            function_name(name),
+            format!("\"{}\",{} ({}){}", name, gate, parameters, result),
+        );
+    }
+    let mut f = File::create(Path::new(&out).join("functions.rs")).unwrap();
+    writeln!(f, "// Generated by build.rs from {} for {}.", REGISTRY, selection).unwrap();
+    writeln!(f, "macro_rules! gl_functions {{").unwrap();
+    writeln!(f, "    ($m:ident) => {{").unwrap();
+    writeln!(f, "        $m! {{").unwrap();
+    for (group, r) in &[("required", true), ("optional", false)] {
+        writeln!(f, "            {} {{", group).unwrap();
+        for (n, e) in entries.get(r).into_iter().flatten() {
+            writeln!(f, "                {}, {};", n, e).unwrap();
+        }
+        writeln!(f, "            }}").unwrap();
+    }
+    writeln!(f, "        }}").unwrap();
+    writeln!(f, "    }};").unwrap();
+    writeln!(f, "}}").unwrap();
+
+    let mut f = File::create(Path::new(&out).join("constants.rs")).unwrap();
+    writeln!(f, "// Generated by build.rs from {} for {}.", REGISTRY, selection).unwrap();
+    for name in &enums {
+        let e = all_enums
+            .get(name)
+            .unwrap_or_else(|| panic!("Enum {} is not in the registry", name));
+        let n = &name[3..];
+        let n = if n.starts_with(|c: char| c.is_numeric()) {
+            name.as_str()
+        } else {
+            n
+        };
+        writeln!(f, "pub const {}: {} = {};", n, e.1, e.0).unwrap();
+    }
+}
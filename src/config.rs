@@ -0,0 +1,257 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Api {
+    Gl,
+    Gles,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Profile {
+    Core,
+    Compatibility,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Robustness {
+    None,
+    NoResetNotification,
+    LoseContextOnReset,
+}
+
+const GL_VERSIONS: [(u8, u8); 13] = [
+    (4, 6),
+    (4, 5),
+    (4, 4),
+    (4, 3),
+    (4, 2),
+    (4, 1),
+    (4, 0),
+    (3, 3),
+    (3, 2),
+    (3, 1),
+    (3, 0),
+    (2, 1),
+    (2, 0),
+];
+const GLES_VERSIONS: [(u8, u8); 4] = [(3, 2), (3, 1), (3, 0), (2, 0)];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextConfig {
+    api: Api,
+    min_version: (u8, u8),
+    max_version: (u8, u8),
+    profile: Profile,
+    forward_compatible: bool,
+    debug: bool,
+    robustness: Robustness,
+    no_error: bool,
+    color_bits: (u8, u8, u8, u8),
+    depth_bits: u8,
+    stencil_bits: u8,
+    srgb: bool,
+    samples: u8,
+}
+
+impl Default for ContextConfig {
+    fn default() -> Self {
+        let api = if cfg!(any(
+            target_os = "android",
+            all(
+                any(feature = "gles20", feature = "gles30", feature = "gles32"),
+                not(any(
+                    feature = "gl21",
+                    feature = "gl33",
+                    feature = "gl45",
+                    feature = "gl46"
+                ))
+            )
+        )) {
+            Api::Gles
+        } else {
+            Api::Gl
+        };
+        Self::new(api)
+    }
+}
+
+impl ContextConfig {
+    pub fn new(api: Api) -> Self {
+        let versions = Self::get_known_versions(api);
+        Self {
+            api,
+            min_version: versions[versions.len() - 1],
+            max_version: versions[0],
+            profile: Profile::Core,
+            forward_compatible: false,
+            debug: false,
+            robustness: Robustness::None,
+            no_error: false,
+            color_bits: (8, 8, 8, 0),
+            depth_bits: 24,
+            stencil_bits: 0,
+            srgb: false,
+            samples: 0,
+        }
+    }
+
+    pub fn api(mut self, api: Api) -> Self {
+        let versions = Self::get_known_versions(api);
+        self.api = api;
+        self.min_version = versions[versions.len() - 1];
+        self.max_version = versions[0];
+        self
+    }
+
+    pub fn version(mut self, major: u8, minor: u8) -> Self {
+        self.min_version = (major, minor);
+        self.max_version = (major, minor);
+        self
+    }
+
+    pub fn min_version(mut self, major: u8, minor: u8) -> Self {
+        self.min_version = (major, minor);
+        self
+    }
+
+    pub fn max_version(mut self, major: u8, minor: u8) -> Self {
+        self.max_version = (major, minor);
+        self
+    }
+
+    pub fn profile(mut self, profile: Profile) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    pub fn forward_compatible(mut self, forward_compatible: bool) -> Self {
+        self.forward_compatible = forward_compatible;
+        self
+    }
+
+    pub fn debug(mut self, debug: bool) -> Self {
+        self.debug = debug;
+        self
+    }
+
+    pub fn robustness(mut self, robustness: Robustness) -> Self {
+        self.robustness = robustness;
+        self
+    }
+
+    pub fn no_error(mut self, no_error: bool) -> Self {
+        self.no_error = no_error;
+        self
+    }
+
+    pub fn color_bits(mut self, red: u8, green: u8, blue: u8, alpha: u8) -> Self {
+        self.color_bits = (red, green, blue, alpha);
+        self
+    }
+
+    pub fn depth_bits(mut self, depth_bits: u8) -> Self {
+        self.depth_bits = depth_bits;
+        self
+    }
+
+    pub fn stencil_bits(mut self, stencil_bits: u8) -> Self {
+        self.stencil_bits = stencil_bits;
+        self
+    }
+
+    pub fn srgb(mut self, srgb: bool) -> Self {
+        self.srgb = srgb;
+        self
+    }
+
+    pub fn samples(mut self, samples: u8) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    pub fn get_api(&self) -> Api {
+        self.api
+    }
+
+    pub fn get_min_version(&self) -> (u8, u8) {
+        self.min_version
+    }
+
+    pub fn get_max_version(&self) -> (u8, u8) {
+        self.max_version
+    }
+
+    pub fn get_profile(&self) -> Profile {
+        self.profile
+    }
+
+    pub fn is_forward_compatible(&self) -> bool {
+        self.forward_compatible
+    }
+
+    pub fn is_debug(&self) -> bool {
+        self.debug
+    }
+
+    pub fn get_robustness(&self) -> Robustness {
+        self.robustness
+    }
+
+    pub fn is_no_error(&self) -> bool {
+        self.no_error
+    }
+
+    pub fn get_color_bits(&self) -> (u8, u8, u8, u8) {
+        self.color_bits
+    }
+
+    pub fn get_depth_bits(&self) -> u8 {
+        self.depth_bits
+    }
+
+    pub fn get_stencil_bits(&self) -> u8 {
+        self.stencil_bits
+    }
+
+    pub fn is_srgb(&self) -> bool {
+        self.srgb
+    }
+
+    pub fn get_samples(&self) -> u8 {
+        self.samples
+    }
+
+    fn get_known_versions(api: Api) -> &'static [(u8, u8)] {
+        match api {
+            Api::Gl => &GL_VERSIONS,
+            Api::Gles => &GLES_VERSIONS,
+        }
+    }
+
+    pub(crate) fn get_versions(&self) -> Vec<(u8, u8)> {
+        Self::get_known_versions(self.api)
+            .iter()
+            .filter(|v| **v >= self.min_version && **v <= self.max_version)
+            .cloned()
+            .collect()
+    }
+
+    pub(crate) fn obtained(
+        &self,
+        version: (u8, u8),
+        color_bits: (u8, u8, u8, u8),
+        depth_bits: u8,
+        stencil_bits: u8,
+        srgb: bool,
+        samples: u8,
+    ) -> Self {
+        Self {
+            min_version: version,
+            max_version: version,
+            color_bits,
+            depth_bits,
+            stencil_bits,
+            srgb,
+            samples,
+            ..self.clone()
+        }
+    }
+}
@@ -1 +1,510 @@
-pub const COLOR_BUFFER_BIT: u32 = 16384;
+#![allow(non_upper_case_globals)]
+
+use {
+    super::types::{BitField, Enumerated},
+    std::{
+        convert::TryFrom,
+        ops::{BitAnd, BitOr, BitOrAssign},
+    },
+};
+
+include!(concat!(env!("OUT_DIR"), "/constants.rs"));
+
+macro_rules! gl_enum {
+    ($name:ident { $($variant:ident = $value:literal,)* }) => {
+        #[repr(u32)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($variant = $value,)*
+        }
+
+        impl From<$name> for Enumerated {
+            fn from(v: $name) -> Self {
+                v as Enumerated
+            }
+        }
+
+        impl TryFrom<Enumerated> for $name {
+            type Error = Enumerated;
+
+            fn try_from(v: Enumerated) -> Result<Self, Enumerated> {
+                match v {
+                    $($value => Ok($name::$variant),)*
+                    _ => Err(v),
+                }
+            }
+        }
+    };
+}
+
+macro_rules! gl_flags {
+    ($name:ident { $($flag:ident = $value:literal,)* }) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+        pub struct $name(BitField);
+
+        impl $name {
+            $(pub const $flag: Self = $name($value);)*
+
+            pub fn empty() -> Self {
+                $name(0)
+            }
+
+            pub fn all() -> Self {
+                $name(0 $(| $value)*)
+            }
+
+            pub fn bits(self) -> BitField {
+                self.0
+            }
+
+            pub fn is_empty(self) -> bool {
+                self.0 == 0
+            }
+
+            pub fn contains(self, other: Self) -> bool {
+                self.0 & other.0 == other.0
+            }
+        }
+
+        impl BitOr for $name {
+            type Output = Self;
+
+            fn bitor(self, other: Self) -> Self {
+                $name(self.0 | other.0)
+            }
+        }
+
+        impl BitOrAssign for $name {
+            fn bitor_assign(&mut self, other: Self) {
+                self.0 |= other.0;
+            }
+        }
+
+        impl BitAnd for $name {
+            type Output = Self;
+
+            fn bitand(self, other: Self) -> Self {
+                $name(self.0 & other.0)
+            }
+        }
+
+        impl From<$name> for BitField {
+            fn from(v: $name) -> Self {
+                v.0
+            }
+        }
+
+        impl TryFrom<BitField> for $name {
+            type Error = BitField;
+
+            fn try_from(v: BitField) -> Result<Self, BitField> {
+                if v & !$name::all().0 == 0 {
+                    Ok($name(v))
+                } else {
+                    Err(v)
+                }
+            }
+        }
+    };
+}
+
+gl_enum!(BufferTarget {
+    Array = 0x8892,
+    AtomicCounter = 0x92C0,
+    CopyRead = 0x8F36,
+    CopyWrite = 0x8F37,
+    DispatchIndirect = 0x90EE,
+    DrawIndirect = 0x8F3F,
+    ElementArray = 0x8893,
+    PixelPack = 0x88EB,
+    PixelUnpack = 0x88EC,
+    Query = 0x9192,
+    ShaderStorage = 0x90D2,
+    Texture = 0x8C2A,
+    TransformFeedback = 0x8C8E,
+    Uniform = 0x8A11,
+});
+
+gl_enum!(TextureTarget {
+    Texture1D = 0x0DE0,
+    Texture1DArray = 0x8C18,
+    Texture2D = 0x0DE1,
+    Texture2DArray = 0x8C1A,
+    Texture2DMultisample = 0x9100,
+    Texture2DMultisampleArray = 0x9102,
+    Texture3D = 0x806F,
+    Buffer = 0x8C2A,
+    CubeMap = 0x8513,
+    CubeMapArray = 0x9009,
+    CubeMapPositiveX = 0x8515,
+    CubeMapNegativeX = 0x8516,
+    CubeMapPositiveY = 0x8517,
+    CubeMapNegativeY = 0x8518,
+    CubeMapPositiveZ = 0x8519,
+    CubeMapNegativeZ = 0x851A,
+    Rectangle = 0x84F5,
+});
+
+gl_enum!(InternalFormat {
+    Red = 0x1903,
+    Rg = 0x8227,
+    Rgb = 0x1907,
+    Rgba = 0x1908,
+    DepthComponent = 0x1902,
+    DepthStencil = 0x84F9,
+    R8 = 0x8229,
+    R8Snorm = 0x8F94,
+    R8I = 0x8231,
+    R8UI = 0x8232,
+    R16F = 0x822D,
+    R16I = 0x8233,
+    R16UI = 0x8234,
+    R32F = 0x822E,
+    R32I = 0x8235,
+    R32UI = 0x8236,
+    Rg8 = 0x822B,
+    Rg8Snorm = 0x8F95,
+    Rg16F = 0x822F,
+    Rg32F = 0x8230,
+    Rg32UI = 0x823C,
+    Rgb8 = 0x8051,
+    Rgb565 = 0x8D62,
+    Rgb16F = 0x881B,
+    Rgb32F = 0x8815,
+    R11FG11FB10F = 0x8C3A,
+    Rgb9E5 = 0x8C3D,
+    Srgb8 = 0x8C41,
+    Rgba4 = 0x8056,
+    Rgb5A1 = 0x8057,
+    Rgba8 = 0x8058,
+    Rgba8Snorm = 0x8F97,
+    Rgba8UI = 0x8D7C,
+    Rgb10A2 = 0x8059,
+    Rgba16F = 0x881A,
+    Rgba32F = 0x8814,
+    Rgba32UI = 0x8D70,
+    Srgb8Alpha8 = 0x8C43,
+    DepthComponent16 = 0x81A5,
+    DepthComponent24 = 0x81A6,
+    DepthComponent32F = 0x8CAC,
+    Depth24Stencil8 = 0x88F0,
+    Depth32FStencil8 = 0x8CAD,
+    StencilIndex8 = 0x8D48,
+});
+
+impl InternalFormat {
+    // The client format and type that pair with this internal format for uploads and read-backs.
+    pub fn get_transfer(self) -> (Enumerated, Enumerated) {
+        use self::InternalFormat::*;
+        match self {
+            Red | R8 => (RED, UNSIGNED_BYTE),
+            R8Snorm => (RED, BYTE),
+            R8I => (RED_INTEGER, BYTE),
+            R8UI => (RED_INTEGER, UNSIGNED_BYTE),
+            R16F => (RED, HALF_FLOAT),
+            R16I => (RED_INTEGER, SHORT),
+            R16UI => (RED_INTEGER, UNSIGNED_SHORT),
+            R32F => (RED, FLOAT),
+            R32I => (RED_INTEGER, INT),
+            R32UI => (RED_INTEGER, UNSIGNED_INT),
+            Rg | Rg8 => (RG, UNSIGNED_BYTE),
+            Rg8Snorm => (RG, BYTE),
+            Rg16F => (RG, HALF_FLOAT),
+            Rg32F => (RG, FLOAT),
+            Rg32UI => (RG_INTEGER, UNSIGNED_INT),
+            Rgb | Rgb8 | Srgb8 => (RGB, UNSIGNED_BYTE),
+            Rgb565 => (RGB, UNSIGNED_SHORT_5_6_5),
+            Rgb16F => (RGB, HALF_FLOAT),
+            Rgb32F => (RGB, FLOAT),
+            R11FG11FB10F => (RGB, UNSIGNED_INT_10F_11F_11F_REV),
+            Rgb9E5 => (RGB, UNSIGNED_INT_5_9_9_9_REV),
+            Rgba | Rgba8 | Srgb8Alpha8 => (RGBA, UNSIGNED_BYTE),
+            Rgba4 => (RGBA, UNSIGNED_SHORT_4_4_4_4),
+            Rgb5A1 => (RGBA, UNSIGNED_SHORT_5_5_5_1),
+            Rgba8Snorm => (RGBA, BYTE),
+            Rgba8UI => (RGBA_INTEGER, UNSIGNED_BYTE),
+            Rgb10A2 => (RGBA, UNSIGNED_INT_2_10_10_10_REV),
+            Rgba16F => (RGBA, HALF_FLOAT),
+            Rgba32F => (RGBA, FLOAT),
+            Rgba32UI => (RGBA_INTEGER, UNSIGNED_INT),
+            DepthComponent | DepthComponent16 => (DEPTH_COMPONENT, UNSIGNED_SHORT),
+            DepthComponent24 => (DEPTH_COMPONENT, UNSIGNED_INT),
+            DepthComponent32F => (DEPTH_COMPONENT, FLOAT),
+            DepthStencil | Depth24Stencil8 => (DEPTH_STENCIL, UNSIGNED_INT_24_8),
+            Depth32FStencil8 => (DEPTH_STENCIL, FLOAT_32_UNSIGNED_INT_24_8_REV),
+            StencilIndex8 => (STENCIL_INDEX, UNSIGNED_BYTE),
+        }
+    }
+
+    // Bytes per pixel in the client memory layout of get_transfer.
+    pub fn get_pixel_size(self) -> usize {
+        let (format, type_) = self.get_transfer();
+        let components = match format {
+            RG | RG_INTEGER => 2,
+            RGB => 3,
+            RGBA | RGBA_INTEGER => 4,
+            _ => 1,
+        };
+        match type_ {
+            BYTE | UNSIGNED_BYTE => components,
+            SHORT | UNSIGNED_SHORT | HALF_FLOAT => components * 2,
+            INT | UNSIGNED_INT | FLOAT => components * 4,
+            UNSIGNED_SHORT_4_4_4_4 | UNSIGNED_SHORT_5_5_5_1 | UNSIGNED_SHORT_5_6_5 => 2,
+            FLOAT_32_UNSIGNED_INT_24_8_REV => 8,
+            _ => 4,
+        }
+    }
+
+    pub fn is_integer(self) -> bool {
+        [RED_INTEGER, RG_INTEGER, RGBA_INTEGER].contains(&self.get_transfer().0)
+    }
+
+    pub fn has_depth(self) -> bool {
+        [DEPTH_COMPONENT, DEPTH_STENCIL].contains(&self.get_transfer().0)
+    }
+
+    pub fn has_stencil(self) -> bool {
+        [DEPTH_STENCIL, STENCIL_INDEX].contains(&self.get_transfer().0)
+    }
+}
+
+gl_enum!(PrimitiveMode {
+    Points = 0,
+    Lines = 0x0001,
+    LineLoop = 0x0002,
+    LineStrip = 0x0003,
+    Triangles = 0x0004,
+    TriangleStrip = 0x0005,
+    TriangleFan = 0x0006,
+    LinesAdjacency = 0x000A,
+    LineStripAdjacency = 0x000B,
+    TrianglesAdjacency = 0x000C,
+    TriangleStripAdjacency = 0x000D,
+    Patches = 0x000E,
+});
+
+gl_enum!(BlendFactor {
+    Zero = 0,
+    One = 1,
+    SrcColor = 0x0300,
+    OneMinusSrcColor = 0x0301,
+    SrcAlpha = 0x0302,
+    OneMinusSrcAlpha = 0x0303,
+    DstAlpha = 0x0304,
+    OneMinusDstAlpha = 0x0305,
+    DstColor = 0x0306,
+    OneMinusDstColor = 0x0307,
+    SrcAlphaSaturate = 0x0308,
+    ConstantColor = 0x8001,
+    OneMinusConstantColor = 0x8002,
+    ConstantAlpha = 0x8003,
+    OneMinusConstantAlpha = 0x8004,
+});
+
+gl_enum!(Capability {
+    Blend = 0x0BE2,
+    CullFace = 0x0B44,
+    DebugOutput = 0x92E0,
+    DebugOutputSynchronous = 0x8242,
+    DepthClamp = 0x864F,
+    DepthTest = 0x0B71,
+    Dither = 0x0BD0,
+    FramebufferSrgb = 0x8DB9,
+    Multisample = 0x809D,
+    PolygonOffsetFill = 0x8037,
+    PrimitiveRestart = 0x8F9D,
+    PrimitiveRestartFixedIndex = 0x8D69,
+    ProgramPointSize = 0x8642,
+    RasterizerDiscard = 0x8C89,
+    SampleAlphaToCoverage = 0x809E,
+    SampleCoverage = 0x80A0,
+    ScissorTest = 0x0C11,
+    StencilTest = 0x0B90,
+    TextureCubeMapSeamless = 0x884F,
+});
+
+gl_enum!(ShaderType {
+    Vertex = 0x8B31,
+    TessControl = 0x8E88,
+    TessEvaluation = 0x8E87,
+    Geometry = 0x8DD9,
+    Fragment = 0x8B30,
+    Compute = 0x91B9,
+});
+
+gl_enum!(ErrorCode {
+    NoError = 0,
+    InvalidEnum = 0x0500,
+    InvalidValue = 0x0501,
+    InvalidOperation = 0x0502,
+    StackOverflow = 0x0503,
+    StackUnderflow = 0x0504,
+    OutOfMemory = 0x0505,
+    InvalidFramebufferOperation = 0x0506,
+    ContextLost = 0x0507,
+});
+
+gl_flags!(ClearMask {
+    COLOR = 0x4000,
+    DEPTH = 0x0100,
+    STENCIL = 0x0400,
+});
+
+gl_flags!(MapAccess {
+    READ = 0x0001,
+    WRITE = 0x0002,
+    INVALIDATE_RANGE = 0x0004,
+    INVALIDATE_BUFFER = 0x0008,
+    FLUSH_EXPLICIT = 0x0010,
+    UNSYNCHRONIZED = 0x0020,
+    PERSISTENT = 0x0040,
+    COHERENT = 0x0080,
+});
+
+gl_enum!(BufferUsage {
+    StreamDraw = 0x88E0,
+    StreamRead = 0x88E1,
+    StreamCopy = 0x88E2,
+    StaticDraw = 0x88E4,
+    StaticRead = 0x88E5,
+    StaticCopy = 0x88E6,
+    DynamicDraw = 0x88E8,
+    DynamicRead = 0x88E9,
+    DynamicCopy = 0x88EA,
+});
+
+gl_enum!(FramebufferTarget {
+    Read = 0x8CA8,
+    Draw = 0x8CA9,
+    Framebuffer = 0x8D40,
+});
+
+gl_enum!(UniformType {
+    Float = 0x1406,
+    FloatVec2 = 0x8B50,
+    FloatVec3 = 0x8B51,
+    FloatVec4 = 0x8B52,
+    Double = 0x140A,
+    DoubleVec2 = 0x8FFC,
+    DoubleVec3 = 0x8FFD,
+    DoubleVec4 = 0x8FFE,
+    Int = 0x1404,
+    IntVec2 = 0x8B53,
+    IntVec3 = 0x8B54,
+    IntVec4 = 0x8B55,
+    UInt = 0x1405,
+    UIntVec2 = 0x8DC6,
+    UIntVec3 = 0x8DC7,
+    UIntVec4 = 0x8DC8,
+    Bool = 0x8B56,
+    BoolVec2 = 0x8B57,
+    BoolVec3 = 0x8B58,
+    BoolVec4 = 0x8B59,
+    FloatMat2 = 0x8B5A,
+    FloatMat3 = 0x8B5B,
+    FloatMat4 = 0x8B5C,
+    FloatMat2x3 = 0x8B65,
+    FloatMat2x4 = 0x8B66,
+    FloatMat3x2 = 0x8B67,
+    FloatMat3x4 = 0x8B68,
+    FloatMat4x2 = 0x8B69,
+    FloatMat4x3 = 0x8B6A,
+    DoubleMat2 = 0x8F46,
+    DoubleMat3 = 0x8F47,
+    DoubleMat4 = 0x8F48,
+    Sampler1D = 0x8B5D,
+    Sampler2D = 0x8B5E,
+    Sampler3D = 0x8B5F,
+    SamplerCube = 0x8B60,
+    Sampler1DShadow = 0x8B61,
+    Sampler2DShadow = 0x8B62,
+    Sampler1DArray = 0x8DC0,
+    Sampler2DArray = 0x8DC1,
+    Sampler1DArrayShadow = 0x8DC3,
+    Sampler2DArrayShadow = 0x8DC4,
+    Sampler2DMultisample = 0x9108,
+    Sampler2DMultisampleArray = 0x910B,
+    SamplerCubeShadow = 0x8DC5,
+    SamplerBuffer = 0x8DC2,
+    Sampler2DRect = 0x8B63,
+    Sampler2DRectShadow = 0x8B64,
+    SamplerCubeMapArray = 0x900C,
+    SamplerCubeMapArrayShadow = 0x900D,
+    IntSampler1D = 0x8DC9,
+    IntSampler2D = 0x8DCA,
+    IntSampler3D = 0x8DCB,
+    IntSamplerCube = 0x8DCC,
+    IntSampler1DArray = 0x8DCE,
+    IntSampler2DArray = 0x8DCF,
+    IntSampler2DMultisample = 0x9109,
+    IntSampler2DMultisampleArray = 0x910C,
+    IntSamplerBuffer = 0x8DD0,
+    IntSampler2DRect = 0x8DCD,
+    IntSamplerCubeMapArray = 0x900E,
+    UIntSampler1D = 0x8DD1,
+    UIntSampler2D = 0x8DD2,
+    UIntSampler3D = 0x8DD3,
+    UIntSamplerCube = 0x8DD4,
+    UIntSampler1DArray = 0x8DD6,
+    UIntSampler2DArray = 0x8DD7,
+    UIntSampler2DMultisample = 0x910A,
+    UIntSampler2DMultisampleArray = 0x910D,
+    UIntSamplerBuffer = 0x8DD8,
+    UIntSampler2DRect = 0x8DD5,
+    UIntSamplerCubeMapArray = 0x900F,
+    Image2D = 0x904D,
+    Image3D = 0x904E,
+    ImageCube = 0x9050,
+    Image2DArray = 0x9053,
+    IntImage2D = 0x9058,
+    IntImage3D = 0x9059,
+    IntImageCube = 0x905B,
+    IntImage2DArray = 0x905E,
+    UIntImage2D = 0x9063,
+    UIntImage3D = 0x9064,
+    UIntImageCube = 0x9066,
+    UIntImage2DArray = 0x9069,
+    UIntAtomicCounter = 0x92DB,
+});
+
+gl_enum!(DebugSource {
+    Api = 0x8246,
+    WindowSystem = 0x8247,
+    ShaderCompiler = 0x8248,
+    ThirdParty = 0x8249,
+    Application = 0x824A,
+    Other = 0x824B,
+});
+
+gl_enum!(DebugType {
+    Error = 0x824C,
+    DeprecatedBehavior = 0x824D,
+    UndefinedBehavior = 0x824E,
+    Portability = 0x824F,
+    Performance = 0x8250,
+    Other = 0x8251,
+    Marker = 0x8268,
+    PushGroup = 0x8269,
+    PopGroup = 0x826A,
+});
+
+gl_enum!(DebugSeverity {
+    High = 0x9146,
+    Medium = 0x9147,
+    Low = 0x9148,
+    Notification = 0x826B,
+});
+
+gl_enum!(ObjectIdentifier {
+    Buffer = 0x82E0,
+    Shader = 0x82E1,
+    Program = 0x82E2,
+    VertexArray = 0x8074,
+    Query = 0x82E3,
+    ProgramPipeline = 0x82E4,
+    TransformFeedback = 0x8E22,
+    Sampler = 0x82E6,
+    Texture = 0x1702,
+    Renderbuffer = 0x8D41,
+    Framebuffer = 0x8D40,
+});
@@ -1,370 +1,1786 @@
+#[cfg(any(target_os = "android", target_os = "linux"))]
+use super::config::{Api, Profile, Robustness};
+#[cfg(all(target_os = "linux", not(feature = "egl")))]
+use crate::{constants::VERSION, info::parse_version};
 #[cfg(feature = "verbose-log")]
 use log::log_i;
-#[cfg(target_os = "windows")]
-use winapi::shared::windef::{HDC, HGLRC};
-#[cfg(target_os = "android")]
+use {
+    super::config::ContextConfig,
+    crate::error::{ContextAttempt, GlError, SwapStatus},
+    crate::window::Window,
+    log::{log_e, log_f},
+    std::{cell::Cell, mem::transmute_copy, ptr::null_mut, sync::Arc},
+};
+#[cfg(any(target_os = "android", target_os = "linux"))]
 use {
     super::egl,
-    log::log_f,
+    std::{cell::RefCell, ffi::CString},
+};
+#[cfg(target_os = "linux")]
+use {
+    super::platform::Platform,
+    crate::window::library_loader::Linker,
     std::{
-        ffi::CString,
-        mem::transmute_copy,
-        ptr::{null, null_mut},
+        ffi::CStr,
+        os::raw::{c_char, c_int, c_uint, c_ulong, c_void},
+    },
+};
+#[cfg(target_os = "windows")]
+use {
+    super::{
+        config::{Api, Profile, Robustness},
+        wgl,
+    },
+    std::{
+        mem::{size_of, zeroed},
+        os::raw::c_int,
+    },
+    winapi::{
+        shared::{
+            minwindef::{FALSE, TRUE, UINT, WORD},
+            windef::{HDC, HGLRC, HWND, RECT},
+        },
+        um::{
+            errhandlingapi::GetLastError,
+            wingdi::{
+                wglCreateContext, wglDeleteContext, wglGetCurrentContext, wglMakeCurrent,
+                wglShareLists, ChoosePixelFormat, DescribePixelFormat, GetPixelFormat,
+                SetPixelFormat, SwapBuffers, PFD_DOUBLEBUFFER, PFD_DRAW_TO_WINDOW,
+                PFD_SUPPORT_OPENGL, PFD_TYPE_RGBA, PIXELFORMATDESCRIPTOR,
+            },
+            winuser::{GetClientRect, GetDC, ReleaseDC},
+        },
     },
 };
-use {crate::window::Window, std::sync::Arc};
 
-pub(crate) struct Context {
-    window: Arc<Window>,
-    #[cfg(target_os = "windows")]
-    device: HDC,
-    #[cfg(target_os = "windows")]
-    render: HGLRC,
-    #[cfg(target_os = "android")]
-    egl_lib: egl::Egl,
-    #[cfg(target_os = "android")]
+thread_local! {
+    // The native handle of our context that is current on this thread, drivers do not tell cheaply.
+    static CURRENT: Cell<usize> = const { Cell::new(0) };
+}
+
+#[cfg(all(feature = "gl-check", debug_assertions))]
+pub(crate) fn get_current_on_thread() -> usize {
+    CURRENT.with(|c| c.get())
+}
+
+fn set_current_on_thread(context: usize) {
+    CURRENT.with(|c| c.set(context));
+}
+
+#[cfg(any(target_os = "android", target_os = "linux"))]
+struct EglState {
+    lib: egl::Egl,
     display: egl::EGLDisplay,
-    #[cfg(target_os = "android")]
     config: egl::EGLConfig,
-    #[cfg(target_os = "android")]
     surface: egl::EGLSurface,
-    #[cfg(target_os = "android")]
     context: egl::EGLContext,
+    desktop: bool,
+    srgb: bool,
+    suspended: bool,
+    swap_interval: i32,
 }
 
-impl Context {
-    #[cfg(target_os = "windows")]
-    pub(crate) fn new(window: Arc<Window>) -> Option<Self> {
-        use std::mem::{size_of, zeroed};
-        use std::ptr::null_mut;
-        use winapi::shared::minwindef::{FALSE, WORD};
-        use winapi::um::wingdi::{
-            wglCreateContext, wglMakeCurrent, ChoosePixelFormat, SetPixelFormat, PFD_DOUBLEBUFFER,
-            PFD_DRAW_TO_BITMAP, PFD_DRAW_TO_WINDOW, PFD_GENERIC_ACCELERATED, PFD_SUPPORT_OPENGL,
-            PFD_SWAP_LAYER_BUFFERS, PFD_TYPE_RGBA, PIXELFORMATDESCRIPTOR,
+#[cfg(any(target_os = "android", target_os = "linux"))]
+impl EglState {
+    fn new(lib: egl::Egl, display: egl::EGLDisplay) -> Result<Self, GlError> {
+        if display.is_null() {
+            return Err(GlError::DisplayNotFound);
+        }
+        if !lib.initialize_display(display) {
+            return Err(GlError::EglInitialization((lib.get_error)()));
+        }
+        Ok(Self {
+            lib,
+            display,
+            config: null_mut(),
+            surface: egl::NO_SURFACE,
+            context: egl::NO_CONTEXT,
+            desktop: false,
+            srgb: false,
+            suspended: false,
+            swap_interval: 1,
+        })
+    }
+
+    fn bind_api(&mut self, api: Api) -> Result<(), GlError> {
+        self.desktop = api == Api::Gl;
+        let api = if self.desktop {
+            egl::OPENGL_API
+        } else {
+            egl::OPENGL_ES_API
         };
-        use winapi::um::winuser::GetDC;
-        let mut desc: PIXELFORMATDESCRIPTOR = unsafe { zeroed() };
-        desc.nSize = size_of::<PIXELFORMATDESCRIPTOR>() as WORD;
-        desc.nVersion = 1;
-        desc.dwFlags = PFD_DRAW_TO_WINDOW
-            | PFD_DRAW_TO_BITMAP
-            | PFD_SUPPORT_OPENGL
-            | PFD_GENERIC_ACCELERATED
-            | PFD_DOUBLEBUFFER
-            | PFD_SWAP_LAYER_BUFFERS;
-        desc.iPixelType = PFD_TYPE_RGBA;
-        desc.cColorBits = 32;
-        desc.cRedBits = 8;
-        desc.cGreenBits = 8;
-        desc.cBlueBits = 8;
-        desc.cAlphaBits = 8;
-        desc.cDepthBits = 32;
-        desc.cStencilBits = 8;
-
-        let device = unsafe { GetDC(sys_app.get_window()) };
-        if device == null_mut() {
-            vxlogi!("Device context fetching failed.");
+        if egl::TRUE != (self.lib.bind_api)(api) {
+            #[cfg(feature = "verbose-log")]
+            log_i!("Can not bind EGL API {}.", api);
+            return Err(GlError::ContextCreation(Vec::new()));
+        }
+        Ok(())
+    }
+
+    fn choose_config(&mut self, config: &ContextConfig, surface_type: egl::EGLint) -> bool {
+        let renderables: &[egl::EGLint] = if self.desktop {
+            &[egl::OPENGL_BIT]
+        } else if config.get_min_version().0 >= 3 {
+            &[egl::OPENGL_ES3_BIT]
+        } else if config.get_max_version().0 >= 3 {
+            &[egl::OPENGL_ES3_BIT, egl::OPENGL_ES2_BIT]
+        } else {
+            &[egl::OPENGL_ES2_BIT]
+        };
+        let (red, green, blue, alpha) = config.get_color_bits();
+        for &renderable in renderables {
+            let attribs = [
+                egl::RENDERABLE_TYPE,
+                renderable,
+                egl::SURFACE_TYPE,
+                surface_type,
+                egl::RED_SIZE,
+                red as egl::EGLint,
+                egl::GREEN_SIZE,
+                green as egl::EGLint,
+                egl::BLUE_SIZE,
+                blue as egl::EGLint,
+                egl::ALPHA_SIZE,
+                alpha as egl::EGLint,
+                egl::DEPTH_SIZE,
+                config.get_depth_bits() as egl::EGLint,
+                egl::STENCIL_SIZE,
+                config.get_stencil_bits() as egl::EGLint,
+                egl::SAMPLE_BUFFERS,
+                if config.get_samples() == 0 { 0 } else { 1 },
+                egl::SAMPLES,
+                config.get_samples() as egl::EGLint,
+                egl::NONE,
+            ];
+            let mut configs = [null_mut(); 64];
+            let mut num_configs = 0;
+            if egl::TRUE
+                != (self.lib.choose_config)(
+                    self.display,
+                    attribs.as_ptr(),
+                    configs.as_mut_ptr(),
+                    configs.len() as egl::EGLint,
+                    &mut num_configs,
+                )
+            {
+                continue;
+            }
+            let configs = &configs[..num_configs.max(0) as usize];
+            let color = |c: egl::EGLConfig| {
+                let get = |attribute| {
+                    let mut value = 0;
+                    (self.lib.get_config_attrib)(self.display, c, attribute, &mut value);
+                    value as u8
+                };
+                (
+                    get(egl::RED_SIZE),
+                    get(egl::GREEN_SIZE),
+                    get(egl::BLUE_SIZE),
+                    get(egl::ALPHA_SIZE),
+                )
+            };
+            if let Some(&c) = configs
+                .iter()
+                .find(|&&c| color(c) == config.get_color_bits())
+                .or_else(|| configs.first())
+            {
+                self.config = c;
+                return true;
+            }
+        }
+        false
+    }
+
+    fn get_surface_attribs(&mut self, config: &ContextConfig) -> Vec<egl::EGLint> {
+        let mut attribs = Vec::new();
+        if config.is_srgb() {
+            if self
+                .lib
+                .has_extension(self.display, "EGL_KHR_gl_colorspace")
+            {
+                attribs.extend_from_slice(&[egl::GL_COLORSPACE, egl::GL_COLORSPACE_SRGB]);
+                self.srgb = true;
+            } else {
+                #[cfg(feature = "verbose-log")]
+                log_i!("EGL_KHR_gl_colorspace is not supported, sRGB surface is ignored.");
+            }
+        }
+        attribs.push(egl::NONE);
+        attribs
+    }
+
+    fn create_context(
+        &mut self,
+        config: &ContextConfig,
+        share: egl::EGLContext,
+    ) -> Result<ContextConfig, GlError> {
+        let mut attempts = Vec::new();
+        for (major, minor) in config.get_versions() {
+            let mut attribs = vec![
+                egl::CONTEXT_MAJOR_VERSION,
+                major as egl::EGLint,
+                egl::CONTEXT_MINOR_VERSION,
+                minor as egl::EGLint,
+            ];
+            if self.desktop && (major, minor) >= (3, 2) {
+                attribs.push(egl::CONTEXT_OPENGL_PROFILE_MASK);
+                attribs.push(match config.get_profile() {
+                    Profile::Core => egl::CONTEXT_OPENGL_CORE_PROFILE_BIT,
+                    Profile::Compatibility => egl::CONTEXT_OPENGL_COMPATIBILITY_PROFILE_BIT,
+                });
+            }
+            if self.desktop && config.is_forward_compatible() {
+                attribs.push(egl::CONTEXT_OPENGL_FORWARD_COMPATIBLE);
+                attribs.push(egl::TRUE as egl::EGLint);
+            }
+            if config.is_debug() {
+                attribs.push(egl::CONTEXT_OPENGL_DEBUG);
+                attribs.push(egl::TRUE as egl::EGLint);
+            }
+            if config.get_robustness() != Robustness::None {
+                attribs.push(egl::CONTEXT_OPENGL_ROBUST_ACCESS);
+                attribs.push(egl::TRUE as egl::EGLint);
+                attribs.push(egl::CONTEXT_OPENGL_RESET_NOTIFICATION_STRATEGY);
+                attribs.push(
+                    if config.get_robustness() == Robustness::NoResetNotification {
+                        egl::NO_RESET_NOTIFICATION
+                    } else {
+                        egl::LOSE_CONTEXT_ON_RESET
+                    },
+                );
+            }
+            if config.is_no_error() {
+                attribs.push(egl::CONTEXT_OPENGL_NO_ERROR);
+                attribs.push(egl::TRUE as egl::EGLint);
+            }
+            attribs.push(egl::NONE);
+            let context =
+                (self.lib.create_context)(self.display, self.config, share, attribs.as_ptr());
+            if !context.is_null()
+                && egl::TRUE
+                    == (self.lib.make_current)(self.display, self.surface, self.surface, context)
+            {
+                #[cfg(feature = "verbose-log")]
+                log_i!(
+                    "EGL context with OpenGL{} {}.{} created",
+                    if self.desktop { "" } else { " ES" },
+                    major,
+                    minor
+                );
+                self.context = context;
+                set_current_on_thread(context as usize);
+                let get = |attribute| {
+                    let mut value = 0;
+                    (self.lib.get_config_attrib)(self.display, self.config, attribute, &mut value);
+                    value as u8
+                };
+                return Ok(config.obtained(
+                    (major, minor),
+                    (
+                        get(egl::RED_SIZE),
+                        get(egl::GREEN_SIZE),
+                        get(egl::BLUE_SIZE),
+                        get(egl::ALPHA_SIZE),
+                    ),
+                    get(egl::DEPTH_SIZE),
+                    get(egl::STENCIL_SIZE),
+                    self.srgb,
+                    get(egl::SAMPLES),
+                ));
+            }
+            attempts.push(ContextAttempt {
+                major: major as i32,
+                minor: minor as i32,
+                code: (self.lib.get_error)(),
+            });
+            if !context.is_null() {
+                (self.lib.destroy_context)(self.display, context);
+            }
+        }
+        #[cfg(feature = "verbose-log")]
+        log_i!("Can not create EGL Context.");
+        Err(GlError::ContextCreation(attempts))
+    }
+
+    fn swap(&self) -> SwapStatus {
+        if self.suspended {
+            return SwapStatus::SurfaceLost;
+        }
+        if self.surface.is_null()
+            || egl::TRUE == (self.lib.swap_buffers)(self.display, self.surface)
+        {
+            return SwapStatus::Ok;
+        }
+        match (self.lib.get_error)() {
+            egl::BAD_SURFACE | egl::BAD_NATIVE_WINDOW => SwapStatus::SurfaceLost,
+            egl::CONTEXT_LOST | egl::BAD_CONTEXT => SwapStatus::ContextLost,
+            e => {
+                log_e!("Unhandled EGL swap error 0x{:X}.", e);
+                SwapStatus::SurfaceLost
+            }
+        }
+    }
+
+    fn get_surface_size(&self) -> Option<(u32, u32)> {
+        if self.surface.is_null() {
             return None;
         }
-        let pixel_format = unsafe { ChoosePixelFormat(device, &desc) };
-        if pixel_format == 0 {
-            vxlogi!("Pixel format index fetching failed.");
+        let mut width = 0;
+        let mut height = 0;
+        if egl::TRUE != (self.lib.query_surface)(self.display, self.surface, egl::WIDTH, &mut width)
+            || egl::TRUE
+                != (self.lib.query_surface)(self.display, self.surface, egl::HEIGHT, &mut height)
+        {
+            return None;
+        }
+        Some((width as u32, height as u32))
+    }
+
+    fn suspend(&mut self) {
+        if !self.surface.is_null() {
+            (self.lib.make_current)(self.display, egl::NO_SURFACE, egl::NO_SURFACE, self.context);
+            (self.lib.destroy_surface)(self.display, self.surface);
+            self.surface = egl::NO_SURFACE;
+        }
+        self.suspended = true;
+    }
+
+    fn resume(
+        &mut self,
+        window: egl::EGLNativeWindowType,
+        config: &ContextConfig,
+    ) -> Result<SwapStatus, GlError> {
+        self.suspend();
+        let attribs = self.get_surface_attribs(config);
+        self.surface =
+            (self.lib.create_window_surface)(self.display, self.config, window, attribs.as_ptr());
+        if self.surface.is_null() {
+            return Err(GlError::SurfaceCreation((self.lib.get_error)()));
+        }
+        self.suspended = false;
+        if egl::TRUE
+            == (self.lib.make_current)(self.display, self.surface, self.surface, self.context)
+        {
+            set_current_on_thread(self.context as usize);
+            // The interval belongs to the surface, so a new surface starts with the default again.
+            if self.swap_interval != 1
+                && egl::TRUE != (self.lib.swap_interval)(self.display, self.swap_interval)
+            {
+                log_e!(
+                    "Restoring EGL swap interval failed with error 0x{:X}.",
+                    (self.lib.get_error)()
+                );
+                self.swap_interval = 1;
+            }
+            return Ok(SwapStatus::Ok);
+        }
+        match (self.lib.get_error)() {
+            egl::CONTEXT_LOST | egl::BAD_CONTEXT => {
+                #[cfg(feature = "verbose-log")]
+                log_i!("EGL context is lost, recreating it.");
+                (self.lib.destroy_context)(self.display, self.context);
+                self.context = egl::NO_CONTEXT;
+                self.create_context(config, egl::NO_CONTEXT)?;
+                Ok(SwapStatus::ContextLost)
+            }
+            e => Err(GlError::MakeCurrent(e)),
+        }
+    }
+
+    fn create_shared(&self, config: &ContextConfig) -> Result<Self, GlError> {
+        let mut state = Self::new(egl::Egl::new()?, self.display)?;
+        state.bind_api(config.get_api())?;
+        state.config = self.config;
+        state.srgb = self.srgb;
+        // A worker never presents, it only needs something to be current with.
+        if !state
+            .lib
+            .has_extension(state.display, "EGL_KHR_surfaceless_context")
+        {
+            let attribs = [egl::WIDTH, 1, egl::HEIGHT, 1, egl::NONE];
+            state.surface =
+                (state.lib.create_pbuffer_surface)(state.display, state.config, attribs.as_ptr());
+            if state.surface.is_null() {
+                return Err(GlError::SurfaceCreation((state.lib.get_error)()));
+            }
+        }
+        state.create_context(config, self.context)?;
+        Ok(state)
+    }
+
+    fn make_current(&self) -> Result<(), GlError> {
+        // The bound API is per thread, a fresh thread starts with OpenGL ES.
+        (self.lib.bind_api)(if self.desktop {
+            egl::OPENGL_API
+        } else {
+            egl::OPENGL_ES_API
+        });
+        if egl::TRUE
+            != (self.lib.make_current)(self.display, self.surface, self.surface, self.context)
+        {
+            return Err(GlError::MakeCurrent((self.lib.get_error)()));
+        }
+        set_current_on_thread(self.context as usize);
+        Ok(())
+    }
+
+    fn release_current(&self) {
+        (self.lib.make_current)(
+            self.display,
+            egl::NO_SURFACE,
+            egl::NO_SURFACE,
+            egl::NO_CONTEXT,
+        );
+        set_current_on_thread(0);
+    }
+
+    fn set_swap_interval(&mut self, interval: i32) -> Option<i32> {
+        let get = |attribute| {
+            let mut value = 0;
+            (self.lib.get_config_attrib)(self.display, self.config, attribute, &mut value);
+            value
+        };
+        // EGL has no adaptive vsync and silently clamps to the config limits.
+        let interval = interval
+            .abs()
+            .max(get(egl::MIN_SWAP_INTERVAL))
+            .min(get(egl::MAX_SWAP_INTERVAL));
+        // Without a surface the interval is kept for the next one and nothing is applied yet.
+        if self.surface.is_null() {
+            self.swap_interval = interval;
             return None;
         }
-        if FALSE == unsafe { SetPixelFormat(device, pixel_format, &desc) } {
-            vxlogi!("Pixel format creation failed.");
+        if egl::TRUE != (self.lib.swap_interval)(self.display, interval) {
+            log_e!(
+                "Setting EGL swap interval failed with error 0x{:X}.",
+                (self.lib.get_error)()
+            );
             return None;
         }
-        vxlogi!("Pixel format created.");
+        self.swap_interval = interval;
+        Some(interval)
+    }
 
-        let render = unsafe { wglCreateContext(device) };
-        if render == null_mut() {
-            vxlogi!("Failed to create rendering context.");
+    fn get_swap_interval(&self) -> Option<i32> {
+        if self.surface.is_null() {
+            None
+        } else {
+            Some(self.swap_interval)
+        }
+    }
+
+    fn get_function<T>(&self, s: &str) -> Option<T> {
+        let cs = CString::new(s).unwrap();
+        if let Some(f) = (self.lib.get_proc_address)(cs.as_ptr()) {
+            Some(unsafe { transmute_copy(&f) })
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(any(target_os = "android", target_os = "linux"))]
+impl Drop for EglState {
+    fn drop(&mut self) {
+        // Dropping a shared context must not release another context current on this thread.
+        if (self.lib.get_current_context)() == self.context {
+            self.release_current();
+        }
+        if !self.context.is_null()
+            && egl::TRUE != (self.lib.destroy_context)(self.display, self.context)
+        {
+            log_f!("Failed to terminate EGL context.");
+        }
+        if !self.surface.is_null()
+            && egl::TRUE != (self.lib.destroy_surface)(self.display, self.surface)
+        {
+            log_f!("Failed to terminate EGL surface.");
+        }
+        if !self.lib.terminate_display(self.display) {
+            log_f!("Failed to terminate EGL.");
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+const GLX_SWAP_INTERVAL_EXT: c_int = 0x20F1;
+#[cfg(target_os = "linux")]
+const GLX_LATE_SWAPS_TEAR_EXT: c_int = 0x20F3;
+
+// Only the swap interval extensions are needed from GLX, the context itself is owned by the window.
+#[cfg(target_os = "linux")]
+struct Glx {
+    display: *mut c_void,
+    drawable: c_ulong,
+    extensions: String,
+    swap_interval_ext: Option<extern "C" fn(*mut c_void, c_ulong, c_int)>,
+    query_drawable: Option<extern "C" fn(*mut c_void, c_ulong, c_int, *mut c_uint)>,
+    swap_interval_mesa: Option<extern "C" fn(c_uint) -> c_int>,
+    get_swap_interval_mesa: Option<extern "C" fn() -> c_int>,
+    swap_interval_sgi: Option<extern "C" fn(c_int) -> c_int>,
+}
+
+#[cfg(target_os = "linux")]
+impl Glx {
+    fn new(window: &Window) -> Option<Self> {
+        let get_display: extern "C" fn() -> *mut c_void =
+            window.get_gl_function("glXGetCurrentDisplay")?;
+        let get_drawable: extern "C" fn() -> c_ulong =
+            window.get_gl_function("glXGetCurrentDrawable")?;
+        let query_extensions: extern "C" fn(*mut c_void, c_int) -> *const c_char =
+            window.get_gl_function("glXQueryExtensionsString")?;
+        let display = get_display();
+        if display.is_null() {
             return None;
         }
-        if FALSE == unsafe { wglMakeCurrent(device, render) } {
-            vxlogi!("Failed to make rendering context current.");
+        // Windows are always created on the default screen.
+        let extensions = query_extensions(display, 0);
+        if extensions.is_null() {
             return None;
         }
+        let extensions = unsafe { CStr::from_ptr(extensions) }
+            .to_string_lossy()
+            .into_owned();
+        let has = |name| extensions.split_whitespace().any(|e| e == name);
+        // glXGetProcAddress hands out stubs for unknown names, so the extension string decides.
+        Some(Self {
+            display,
+            drawable: get_drawable(),
+            swap_interval_ext: if has("GLX_EXT_swap_control") {
+                window.get_gl_function("glXSwapIntervalEXT")
+            } else {
+                None
+            },
+            query_drawable: window.get_gl_function("glXQueryDrawable"),
+            swap_interval_mesa: if has("GLX_MESA_swap_control") {
+                window.get_gl_function("glXSwapIntervalMESA")
+            } else {
+                None
+            },
+            get_swap_interval_mesa: if has("GLX_MESA_swap_control") {
+                window.get_gl_function("glXGetSwapIntervalMESA")
+            } else {
+                None
+            },
+            swap_interval_sgi: if has("GLX_SGI_swap_control") {
+                window.get_gl_function("glXSwapIntervalSGI")
+            } else {
+                None
+            },
+            extensions,
+        })
+    }
+
+    fn has_extension(&self, name: &str) -> bool {
+        self.extensions.split_whitespace().any(|e| e == name)
+    }
+
+    fn set_swap_interval(&self, interval: i32) -> Option<i32> {
+        if let Some(swap_interval) = self.swap_interval_ext {
+            let interval = if interval < 0 && !self.has_extension("GLX_EXT_swap_control_tear") {
+                -interval
+            } else {
+                interval
+            };
+            swap_interval(self.display, self.drawable, interval);
+            return self.get_swap_interval().or(Some(interval));
+        }
+        let interval = interval.abs();
+        if let Some(swap_interval) = self.swap_interval_mesa {
+            if swap_interval(interval as c_uint) == 0 {
+                return self.get_swap_interval().or(Some(interval));
+            }
+        } else if let Some(swap_interval) = self.swap_interval_sgi {
+            // SGI can not turn vsync off.
+            if interval > 0 && swap_interval(interval) == 0 {
+                return Some(interval);
+            }
+        }
+        None
+    }
 
-        Some(Self { device, render })
+    fn get_swap_interval(&self) -> Option<i32> {
+        if let (Some(_), Some(query_drawable)) = (self.swap_interval_ext, self.query_drawable) {
+            let mut interval = 0;
+            query_drawable(
+                self.display,
+                self.drawable,
+                GLX_SWAP_INTERVAL_EXT,
+                &mut interval,
+            );
+            let mut tear = 0;
+            if self.has_extension("GLX_EXT_swap_control_tear") {
+                query_drawable(
+                    self.display,
+                    self.drawable,
+                    GLX_LATE_SWAPS_TEAR_EXT,
+                    &mut tear,
+                );
+            }
+            return Some(if tear != 0 {
+                -(interval as i32)
+            } else {
+                interval as i32
+            });
+        }
+        self.get_swap_interval_mesa.map(|f| f())
     }
+}
 
-    #[cfg(target_os = "windows")]
-    pub(crate) fn swap(&self) {
-        use winapi::shared::minwindef::FALSE;
-        use winapi::um::wingdi::SwapBuffers;
-        if FALSE == unsafe { SwapBuffers(self.device) } {
-            vxlogf!("Context swap failed.");
+#[cfg(all(target_os = "linux", not(feature = "egl")))]
+const GLX_RED_SIZE: c_int = 8;
+#[cfg(all(target_os = "linux", not(feature = "egl")))]
+const GLX_GREEN_SIZE: c_int = 9;
+#[cfg(all(target_os = "linux", not(feature = "egl")))]
+const GLX_BLUE_SIZE: c_int = 10;
+#[cfg(all(target_os = "linux", not(feature = "egl")))]
+const GLX_ALPHA_SIZE: c_int = 11;
+#[cfg(all(target_os = "linux", not(feature = "egl")))]
+const GLX_DEPTH_SIZE: c_int = 12;
+#[cfg(all(target_os = "linux", not(feature = "egl")))]
+const GLX_STENCIL_SIZE: c_int = 13;
+#[cfg(all(target_os = "linux", not(feature = "egl")))]
+const GLX_SAMPLES: c_int = 100_001;
+#[cfg(all(target_os = "linux", not(feature = "egl")))]
+const GLX_FRAMEBUFFER_SRGB_CAPABLE_ARB: c_int = 0x20B2;
+#[cfg(target_os = "linux")]
+const GLX_SCREEN: c_int = 0x800C;
+#[cfg(target_os = "linux")]
+const GLX_FBCONFIG_ID: c_int = 0x8013;
+#[cfg(target_os = "linux")]
+const GLX_RGBA_TYPE: c_int = 0x8014;
+#[cfg(target_os = "linux")]
+const GLX_PBUFFER_HEIGHT: c_int = 0x8040;
+#[cfg(target_os = "linux")]
+const GLX_PBUFFER_WIDTH: c_int = 0x8041;
+#[cfg(target_os = "linux")]
+const GLX_CONTEXT_MAJOR_VERSION_ARB: c_int = 0x2091;
+#[cfg(target_os = "linux")]
+const GLX_CONTEXT_MINOR_VERSION_ARB: c_int = 0x2092;
+#[cfg(target_os = "linux")]
+const GLX_CONTEXT_FLAGS_ARB: c_int = 0x2094;
+#[cfg(target_os = "linux")]
+const GLX_CONTEXT_PROFILE_MASK_ARB: c_int = 0x9126;
+#[cfg(target_os = "linux")]
+const GLX_CONTEXT_DEBUG_BIT_ARB: c_int = 0x0001;
+#[cfg(target_os = "linux")]
+const GLX_CONTEXT_FORWARD_COMPATIBLE_BIT_ARB: c_int = 0x0002;
+#[cfg(target_os = "linux")]
+const GLX_CONTEXT_ROBUST_ACCESS_BIT_ARB: c_int = 0x0004;
+#[cfg(target_os = "linux")]
+const GLX_CONTEXT_CORE_PROFILE_BIT_ARB: c_int = 0x0001;
+#[cfg(target_os = "linux")]
+const GLX_CONTEXT_COMPATIBILITY_PROFILE_BIT_ARB: c_int = 0x0002;
+#[cfg(target_os = "linux")]
+const GLX_CONTEXT_ES2_PROFILE_BIT_EXT: c_int = 0x0004;
+#[cfg(target_os = "linux")]
+const GLX_CONTEXT_RESET_NOTIFICATION_STRATEGY_ARB: c_int = 0x8256;
+#[cfg(target_os = "linux")]
+const GLX_LOSE_CONTEXT_ON_RESET_ARB: c_int = 0x8252;
+#[cfg(target_os = "linux")]
+const GLX_NO_RESET_NOTIFICATION_ARB: c_int = 0x8261;
+#[cfg(target_os = "linux")]
+const GLX_CONTEXT_OPENGL_NO_ERROR_ARB: c_int = 0x31B3;
+
+#[cfg(target_os = "linux")]
+struct GlxContext {
+    display: *mut c_void,
+    drawable: c_ulong,
+    context: *mut c_void,
+    owned: bool,
+    make_current: extern "C" fn(*mut c_void, c_ulong, c_ulong, *mut c_void) -> c_int,
+    get_current_context: extern "C" fn() -> *mut c_void,
+    get_proc_address: extern "C" fn(*const c_char) -> *const c_void,
+    destroy_context: Option<extern "C" fn(*mut c_void, *mut c_void)>,
+    destroy_pbuffer: Option<extern "C" fn(*mut c_void, c_ulong)>,
+    // Only the window's context creates shared ones, so only it keeps XFree around.
+    free: Option<extern "C" fn(*mut c_void) -> c_int>,
+    // Shared contexts have their own display connection, Xlib is not initialized for threads.
+    close_display: Option<extern "C" fn(*mut c_void) -> c_int>,
+    _x11: Option<Linker>,
+}
+
+#[cfg(target_os = "linux")]
+impl GlxContext {
+    // The window creates and owns its GLX context, so it is captured while it is still current.
+    #[cfg(not(feature = "egl"))]
+    fn current(window: &Window) -> Option<Self> {
+        let get_display: extern "C" fn() -> *mut c_void =
+            window.get_gl_function("glXGetCurrentDisplay")?;
+        let get_drawable: extern "C" fn() -> c_ulong =
+            window.get_gl_function("glXGetCurrentDrawable")?;
+        let get_current_context: extern "C" fn() -> *mut c_void =
+            window.get_gl_function("glXGetCurrentContext")?;
+        let context = get_current_context();
+        if context.is_null() {
+            return None;
         }
+        let x11 = Linker::new("libX11.so.6");
+        Some(Self {
+            display: get_display(),
+            drawable: get_drawable(),
+            context,
+            owned: false,
+            make_current: window.get_gl_function("glXMakeContextCurrent")?,
+            get_current_context,
+            get_proc_address: window.get_gl_function("glXGetProcAddressARB")?,
+            destroy_context: None,
+            destroy_pbuffer: None,
+            free: x11.as_ref().and_then(|x11| x11.get_function("XFree")),
+            close_display: None,
+            _x11: x11,
+        })
     }
 
-    #[cfg(target_os = "windows")]
     fn get_function<T>(&self, s: &str) -> Option<T> {
-        use winapi::um::wingdi::wglGetProcAddress;
         let cs = CString::new(s).unwrap();
-        let f = unsafe { wglGetProcAddress(cs.as_ptr()) };
+        let f = (self.get_proc_address)(cs.as_ptr());
         if f.is_null() {
-            return None;
+            None
+        } else {
+            Some(unsafe { transmute_copy(&f) })
         }
-        Some(unsafe { transmute(f) })
     }
 
-    #[cfg(target_os = "linux")]
-    pub fn new(window: Arc<Window>) -> Option<Self> {
-        Some(Self { window })
+    // The config of this context as seen from display, with the screen it is on.
+    fn get_fb_config(&self, display: *mut c_void) -> Result<(*mut c_void, c_int), GlError> {
+        let query_context: extern "C" fn(*mut c_void, *mut c_void, c_int, *mut c_int) -> c_int =
+            self.get_function("glXQueryContext")
+                .ok_or(GlError::FunctionNotFound("glXQueryContext"))?;
+        let choose_fb_config: extern "C" fn(
+            *mut c_void,
+            c_int,
+            *const c_int,
+            *mut c_int,
+        ) -> *mut *mut c_void = self
+            .get_function("glXChooseFBConfig")
+            .ok_or(GlError::FunctionNotFound("glXChooseFBConfig"))?;
+        let free = self.free.ok_or(GlError::FunctionNotFound("XFree"))?;
+
+        let mut id = 0;
+        let mut screen = 0;
+        query_context(self.display, self.context, GLX_FBCONFIG_ID, &mut id);
+        query_context(self.display, self.context, GLX_SCREEN, &mut screen);
+        let attribs = [GLX_FBCONFIG_ID, id, 0];
+        let mut count = 0;
+        let configs = choose_fb_config(display, screen, attribs.as_ptr(), &mut count);
+        if configs.is_null() || count == 0 {
+            return Err(GlError::NoMatchingConfig);
+        }
+        let fb_config = unsafe { *configs };
+        free(configs as *mut c_void);
+        Ok((fb_config, screen))
     }
 
-    #[cfg(target_os = "linux")]
-    pub fn swap(&self) {
-        self.window.swap();
+    // The window picked the framebuffer config and version, so they are read back from the
+    // current context instead of being taken from the request.
+    #[cfg(not(feature = "egl"))]
+    fn get_config(&self, config: &ContextConfig) -> Result<ContextConfig, GlError> {
+        let get_fb_config_attrib: extern "C" fn(
+            *mut c_void,
+            *mut c_void,
+            c_int,
+            *mut c_int,
+        ) -> c_int = self
+            .get_function("glXGetFBConfigAttrib")
+            .ok_or(GlError::FunctionNotFound("glXGetFBConfigAttrib"))?;
+        let get_string: extern "C" fn(u32) -> *const c_char = self
+            .get_function("glGetString")
+            .ok_or(GlError::FunctionNotFound("glGetString"))?;
+        let (fb_config, _) = self.get_fb_config(self.display)?;
+        let get = |attribute| {
+            let mut value = 0;
+            get_fb_config_attrib(self.display, fb_config, attribute, &mut value);
+            value as u8
+        };
+        let version = get_string(VERSION);
+        if version.is_null() {
+            return Err(GlError::ContextCreation(Vec::new()));
+        }
+        let (_, version) = parse_version(&unsafe { CStr::from_ptr(version) }.to_string_lossy());
+        Ok(config.obtained(
+            version,
+            (
+                get(GLX_RED_SIZE),
+                get(GLX_GREEN_SIZE),
+                get(GLX_BLUE_SIZE),
+                get(GLX_ALPHA_SIZE),
+            ),
+            get(GLX_DEPTH_SIZE),
+            get(GLX_STENCIL_SIZE),
+            get(GLX_FRAMEBUFFER_SRGB_CAPABLE_ARB) != 0,
+            get(GLX_SAMPLES),
+        ))
     }
 
-    #[cfg(target_os = "linux")]
-    pub fn get_function<T>(&self, s: &str) -> Option<T> {
-        self.window.get_gl_function(s)
+    // The shared context gets its own display connection and pbuffer, so the thread it moves to
+    // shares no Xlib state with the window's thread.
+    fn create_shared(&self, config: &ContextConfig) -> Result<Self, GlError> {
+        macro_rules! fun {
+            ($n:expr) => {
+                if let Some(f) = self.get_function($n) {
+                    f
+                } else {
+                    return Err(GlError::FunctionNotFound($n));
+                }
+            };
+        }
+        let x11 = Linker::new("libX11.so.6").ok_or(GlError::LibraryNotFound("libX11.so.6"))?;
+        let open_display: extern "C" fn(*const c_char) -> *mut c_void = x11
+            .get_function("XOpenDisplay")
+            .ok_or(GlError::FunctionNotFound("XOpenDisplay"))?;
+        let display_string: extern "C" fn(*mut c_void) -> *const c_char = x11
+            .get_function("XDisplayString")
+            .ok_or(GlError::FunctionNotFound("XDisplayString"))?;
+        let close_display: extern "C" fn(*mut c_void) -> c_int = x11
+            .get_function("XCloseDisplay")
+            .ok_or(GlError::FunctionNotFound("XCloseDisplay"))?;
+        let query_extensions: extern "C" fn(*mut c_void, c_int) -> *const c_char =
+            fun!("glXQueryExtensionsString");
+        let create_new_context: extern "C" fn(
+            *mut c_void,
+            *mut c_void,
+            c_int,
+            *mut c_void,
+            c_int,
+        ) -> *mut c_void = fun!("glXCreateNewContext");
+        let create_pbuffer: extern "C" fn(*mut c_void, *mut c_void, *const c_int) -> c_ulong =
+            fun!("glXCreatePbuffer");
+        let display = open_display(display_string(self.display));
+        if display.is_null() {
+            return Err(GlError::DisplayNotFound);
+        }
+        let mut shared = Self {
+            display,
+            drawable: 0,
+            context: null_mut(),
+            owned: true,
+            make_current: self.make_current,
+            get_current_context: self.get_current_context,
+            get_proc_address: self.get_proc_address,
+            destroy_context: Some(fun!("glXDestroyContext")),
+            destroy_pbuffer: Some(fun!("glXDestroyPbuffer")),
+            free: None,
+            close_display: Some(close_display),
+            _x11: Some(x11),
+        };
+        let (fb_config, screen) = self.get_fb_config(display)?;
+        let extensions = query_extensions(display, screen);
+        let extensions = if extensions.is_null() {
+            String::new()
+        } else {
+            unsafe { CStr::from_ptr(extensions) }
+                .to_string_lossy()
+                .into_owned()
+        };
+        let has = |name| extensions.split_whitespace().any(|e| e == name);
+        let create_context_attribs: Option<
+            extern "C" fn(
+                *mut c_void,
+                *mut c_void,
+                *mut c_void,
+                c_int,
+                *const c_int,
+            ) -> *mut c_void,
+        > = if has("GLX_ARB_create_context") {
+            self.get_function("glXCreateContextAttribsARB")
+        } else {
+            None
+        };
+        let mut attempts = Vec::new();
+        let versions = config.get_versions();
+        let desktop = config.get_api() == Api::Gl;
+        match create_context_attribs {
+            Some(create) if desktop || has("GLX_EXT_create_context_es2_profile") => {
+                let robustness = config.get_robustness() != Robustness::None
+                    && has("GLX_ARB_create_context_robustness");
+                let no_error = config.is_no_error() && has("GLX_ARB_create_context_no_error");
+                for (major, minor) in versions {
+                    let mut attribs = vec![
+                        GLX_CONTEXT_MAJOR_VERSION_ARB,
+                        major as c_int,
+                        GLX_CONTEXT_MINOR_VERSION_ARB,
+                        minor as c_int,
+                    ];
+                    if !desktop {
+                        attribs.push(GLX_CONTEXT_PROFILE_MASK_ARB);
+                        attribs.push(GLX_CONTEXT_ES2_PROFILE_BIT_EXT);
+                    } else if (major, minor) >= (3, 2) {
+                        attribs.push(GLX_CONTEXT_PROFILE_MASK_ARB);
+                        attribs.push(match config.get_profile() {
+                            Profile::Core => GLX_CONTEXT_CORE_PROFILE_BIT_ARB,
+                            Profile::Compatibility => GLX_CONTEXT_COMPATIBILITY_PROFILE_BIT_ARB,
+                        });
+                    }
+                    let mut flags = 0;
+                    if desktop && config.is_forward_compatible() {
+                        flags |= GLX_CONTEXT_FORWARD_COMPATIBLE_BIT_ARB;
+                    }
+                    if config.is_debug() {
+                        flags |= GLX_CONTEXT_DEBUG_BIT_ARB;
+                    }
+                    if robustness {
+                        flags |= GLX_CONTEXT_ROBUST_ACCESS_BIT_ARB;
+                        attribs.push(GLX_CONTEXT_RESET_NOTIFICATION_STRATEGY_ARB);
+                        attribs.push(
+                            if config.get_robustness() == Robustness::NoResetNotification {
+                                GLX_NO_RESET_NOTIFICATION_ARB
+                            } else {
+                                GLX_LOSE_CONTEXT_ON_RESET_ARB
+                            },
+                        );
+                    }
+                    if flags != 0 {
+                        attribs.push(GLX_CONTEXT_FLAGS_ARB);
+                        attribs.push(flags);
+                    }
+                    if no_error {
+                        attribs.push(GLX_CONTEXT_OPENGL_NO_ERROR_ARB);
+                        attribs.push(1);
+                    }
+                    attribs.push(0);
+                    shared.context = create(display, fb_config, self.context, 1, attribs.as_ptr());
+                    if !shared.context.is_null() {
+                        break;
+                    }
+                    attempts.push(ContextAttempt {
+                        major: major as i32,
+                        minor: minor as i32,
+                        code: 0,
+                    });
+                }
+            }
+            // Without GLX_ARB_create_context only a legacy context is possible.
+            None if desktop => {
+                if let Some(&(major, minor)) = versions.iter().find(|&&v| v <= (2, 1)) {
+                    shared.context =
+                        create_new_context(display, fb_config, GLX_RGBA_TYPE, self.context, 1);
+                    if shared.context.is_null() {
+                        attempts.push(ContextAttempt {
+                            major: major as i32,
+                            minor: minor as i32,
+                            code: 0,
+                        });
+                    }
+                }
+            }
+            _ => (),
+        }
+        if shared.context.is_null() {
+            #[cfg(feature = "verbose-log")]
+            log_i!("Can not create shared GLX Context.");
+            return Err(GlError::ContextCreation(attempts));
+        }
+        let attribs = [GLX_PBUFFER_WIDTH, 1, GLX_PBUFFER_HEIGHT, 1, 0];
+        shared.drawable = create_pbuffer(display, fb_config, attribs.as_ptr());
+        if shared.drawable == 0 {
+            return Err(GlError::SurfaceCreation(0));
+        }
+        Ok(shared)
     }
 
-    #[cfg(target_os = "android")]
-    pub fn new(window: Arc<Window>) -> Option<Self> {
-        use std::{
-            mem::transmute_copy,
-            ptr::{null, null_mut},
+    fn make_current(&self) -> Result<(), GlError> {
+        if 0 == (self.make_current)(self.display, self.drawable, self.drawable, self.context) {
+            return Err(GlError::MakeCurrent(0));
+        }
+        set_current_on_thread(self.context as usize);
+        Ok(())
+    }
+
+    fn release_current(&self) {
+        (self.make_current)(self.display, 0, 0, null_mut());
+        set_current_on_thread(0);
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for GlxContext {
+    fn drop(&mut self) {
+        if !self.owned {
+            return;
+        }
+        // A creation that failed half way has no context, and must not release the caller's.
+        if !self.context.is_null() && (self.get_current_context)() == self.context {
+            self.release_current();
+        }
+        if let Some(destroy_pbuffer) = self.destroy_pbuffer {
+            if self.drawable != 0 {
+                destroy_pbuffer(self.display, self.drawable);
+            }
+        }
+        if let Some(destroy_context) = self.destroy_context {
+            if !self.context.is_null() {
+                destroy_context(self.display, self.context);
+            }
+        }
+        if let Some(close_display) = self.close_display {
+            close_display(self.display);
+        }
+    }
+}
+
+pub(crate) struct Context {
+    window: Option<Arc<Window>>,
+    #[cfg(target_os = "windows")]
+    hwnd: HWND,
+    #[cfg(target_os = "windows")]
+    device: HDC,
+    #[cfg(target_os = "windows")]
+    render: HGLRC,
+    #[cfg(target_os = "windows")]
+    wgl: wgl::Wgl,
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    egl: Option<RefCell<EglState>>,
+    #[cfg(target_os = "linux")]
+    glx: Option<GlxContext>,
+    config: ContextConfig,
+}
+
+impl Context {
+    #[cfg(target_os = "windows")]
+    pub(crate) fn new(window: Arc<Window>, config: &ContextConfig) -> Result<Self, GlError> {
+        let lib = wgl::Wgl::new()?;
+        let hwnd: HWND = unsafe { transmute_copy(&window.get_window()) };
+        let device = unsafe { GetDC(hwnd) };
+        if device.is_null() {
+            log_e!("Device context fetching failed.");
+            return Err(GlError::DeviceContext);
+        }
+        let mut context = Self {
+            window: Some(window),
+            hwnd,
+            device,
+            render: null_mut(),
+            wgl: lib,
+            config: config.clone(),
         };
+        let (srgb, samples) = context.set_pixel_format(config)?;
+        let version = context.create_context(config, null_mut())?;
+        if let Some(swap_interval) = context.wgl.swap_interval {
+            swap_interval(1);
+        }
+        let mut desc: PIXELFORMATDESCRIPTOR = unsafe { zeroed() };
+        unsafe {
+            DescribePixelFormat(
+                device,
+                GetPixelFormat(device),
+                size_of::<PIXELFORMATDESCRIPTOR>() as UINT,
+                &mut desc,
+            )
+        };
+        context.config = config.obtained(
+            version,
+            (
+                desc.cRedBits,
+                desc.cGreenBits,
+                desc.cBlueBits,
+                desc.cAlphaBits,
+            ),
+            desc.cDepthBits,
+            desc.cStencilBits,
+            srgb,
+            samples,
+        );
+        #[cfg(feature = "verbose-log")]
+        log_i!("WGL context created.");
+        Ok(context)
+    }
 
-        let egl_lib = if let Some(l) = egl::Egl::new() {
-            l
+    #[cfg(target_os = "windows")]
+    fn set_pixel_format(&self, config: &ContextConfig) -> Result<(bool, u8), GlError> {
+        let (red, green, blue, alpha) = config.get_color_bits();
+        let srgb = config.is_srgb()
+            && (self.wgl.has_extension("WGL_ARB_framebuffer_sRGB")
+                || self.wgl.has_extension("WGL_EXT_framebuffer_sRGB"));
+        let samples = if self.wgl.has_extension("WGL_ARB_multisample") {
+            config.get_samples()
         } else {
-            return None;
+            0
         };
-        let display = (egl_lib.get_display)(egl::DEFAULT_DISPLAY);
-        if egl::TRUE != (egl_lib.initialize)(display, null_mut(), null_mut()) {
-            return None;
-        }
-        const EGL_CONFIGS: [[egl::EGLint; 3]; 8] = [
-            [egl::OPENGL_ES3_BIT, 32, 4],
-            [egl::OPENGL_ES3_BIT, 32, 0],
-            [egl::OPENGL_ES3_BIT, 24, 4],
-            [egl::OPENGL_ES3_BIT, 24, 0],
-            [egl::OPENGL_ES2_BIT, 32, 4],
-            [egl::OPENGL_ES2_BIT, 32, 0],
-            [egl::OPENGL_ES2_BIT, 24, 4],
-            [egl::OPENGL_ES2_BIT, 24, 0],
+        let mut attribs = vec![
+            wgl::DRAW_TO_WINDOW_ARB,
+            TRUE,
+            wgl::SUPPORT_OPENGL_ARB,
+            TRUE,
+            wgl::DOUBLE_BUFFER_ARB,
+            TRUE,
+            wgl::ACCELERATION_ARB,
+            wgl::FULL_ACCELERATION_ARB,
+            wgl::PIXEL_TYPE_ARB,
+            wgl::TYPE_RGBA_ARB,
+            wgl::RED_BITS_ARB,
+            red as c_int,
+            wgl::GREEN_BITS_ARB,
+            green as c_int,
+            wgl::BLUE_BITS_ARB,
+            blue as c_int,
+            wgl::ALPHA_BITS_ARB,
+            alpha as c_int,
+            wgl::DEPTH_BITS_ARB,
+            config.get_depth_bits() as c_int,
+            wgl::STENCIL_BITS_ARB,
+            config.get_stencil_bits() as c_int,
         ];
-        let mut config = null_mut();
-        let mut surface = null_mut();
-        for c in &EGL_CONFIGS {
-            if {
-                let attribs = [
-                    egl::RENDERABLE_TYPE,
-                    c[0],
-                    egl::SURFACE_TYPE,
-                    egl::WINDOW_BIT,
-                    egl::BLUE_SIZE,
-                    8,
-                    egl::GREEN_SIZE,
-                    8,
-                    egl::RED_SIZE,
-                    8,
-                    egl::DEPTH_SIZE,
-                    c[1],
-                    egl::SAMPLE_BUFFERS,
-                    if c[2] == 0 { 0 } else { 1 },
-                    egl::SAMPLES,
-                    c[2],
-                    egl::NONE,
-                ];
-                let mut num_configs = 0;
-                egl::TRUE
-                    == (egl_lib.choose_config)(
-                        display,
-                        attribs.as_ptr(),
-                        &mut config,
-                        1,
-                        &mut num_configs,
-                    )
-                    && num_configs > 0
-                    && !config.is_null()
-            } {
-                surface = (egl_lib.create_window_surface)(
-                    display,
-                    config,
-                    unsafe { transmute_copy(&window.get_window()) },
-                    null(),
+        if srgb {
+            attribs.extend_from_slice(&[wgl::FRAMEBUFFER_SRGB_CAPABLE_ARB, TRUE]);
+        }
+        if samples > 0 {
+            attribs.extend_from_slice(&[
+                wgl::SAMPLE_BUFFERS_ARB,
+                1,
+                wgl::SAMPLES_ARB,
+                samples as c_int,
+            ]);
+        }
+        attribs.push(0);
+        let mut desc: PIXELFORMATDESCRIPTOR = unsafe { zeroed() };
+        desc.nSize = size_of::<PIXELFORMATDESCRIPTOR>() as WORD;
+        desc.nVersion = 1;
+        desc.dwFlags = PFD_DRAW_TO_WINDOW | PFD_SUPPORT_OPENGL | PFD_DOUBLEBUFFER;
+        desc.iPixelType = PFD_TYPE_RGBA;
+        desc.cColorBits = red + green + blue;
+        desc.cRedBits = red;
+        desc.cGreenBits = green;
+        desc.cBlueBits = blue;
+        desc.cAlphaBits = alpha;
+        desc.cDepthBits = config.get_depth_bits();
+        desc.cStencilBits = config.get_stencil_bits();
+        let (pixel_format, srgb, samples) = match self.wgl.find_pixel_format(self.device, &attribs)
+        {
+            Some(f) => (f, srgb, samples),
+            None => {
+                #[cfg(feature = "verbose-log")]
+                log_i!("WGL_ARB_pixel_format is not usable, falling back to ChoosePixelFormat.");
+                (unsafe { ChoosePixelFormat(self.device, &desc) }, false, 0)
+            }
+        };
+        if pixel_format == 0 {
+            log_e!("Pixel format index fetching failed.");
+            return Err(GlError::PixelFormat);
+        }
+        unsafe {
+            DescribePixelFormat(
+                self.device,
+                pixel_format,
+                size_of::<PIXELFORMATDESCRIPTOR>() as UINT,
+                &mut desc,
+            )
+        };
+        if FALSE == unsafe { SetPixelFormat(self.device, pixel_format, &desc) } {
+            log_e!("Pixel format creation failed.");
+            return Err(GlError::PixelFormat);
+        }
+        Ok((srgb, samples))
+    }
+
+    #[cfg(target_os = "windows")]
+    fn create_context(
+        &mut self,
+        config: &ContextConfig,
+        share: HGLRC,
+    ) -> Result<(u8, u8), GlError> {
+        let mut attempts = Vec::new();
+        let versions = config.get_versions();
+        let create = match self.wgl.create_context_attribs {
+            Some(create) => create,
+            None => {
+                // Without WGL_ARB_create_context only a legacy context is possible.
+                let version = versions.iter().find(|&&v| v <= (2, 1));
+                if let (Api::Gl, Some(&version)) = (config.get_api(), version) {
+                    let render = unsafe { wglCreateContext(self.device) };
+                    if !render.is_null()
+                        && (share.is_null() || FALSE != unsafe { wglShareLists(share, render) })
+                        && FALSE != unsafe { wglMakeCurrent(self.device, render) }
+                    {
+                        self.render = render;
+                        set_current_on_thread(render as usize);
+                        return Ok(version);
+                    }
+                    if !render.is_null() {
+                        unsafe { wglDeleteContext(render) };
+                    }
+                }
+                #[cfg(feature = "verbose-log")]
+                log_i!("WGL_ARB_create_context is not supported.");
+                return Err(GlError::ContextCreation(attempts));
+            }
+        };
+        let desktop = config.get_api() == Api::Gl;
+        if !desktop && !self.wgl.has_extension("WGL_EXT_create_context_es2_profile") {
+            #[cfg(feature = "verbose-log")]
+            log_i!("WGL_EXT_create_context_es2_profile is not supported.");
+            return Err(GlError::ContextCreation(attempts));
+        }
+        let robustness = config.get_robustness() != Robustness::None
+            && self.wgl.has_extension("WGL_ARB_create_context_robustness");
+        let no_error =
+            config.is_no_error() && self.wgl.has_extension("WGL_ARB_create_context_no_error");
+        for (major, minor) in versions {
+            let mut attribs = vec![
+                wgl::CONTEXT_MAJOR_VERSION_ARB,
+                major as c_int,
+                wgl::CONTEXT_MINOR_VERSION_ARB,
+                minor as c_int,
+            ];
+            if !desktop {
+                attribs.push(wgl::CONTEXT_PROFILE_MASK_ARB);
+                attribs.push(wgl::CONTEXT_ES2_PROFILE_BIT_EXT);
+            } else if (major, minor) >= (3, 2) {
+                attribs.push(wgl::CONTEXT_PROFILE_MASK_ARB);
+                attribs.push(match config.get_profile() {
+                    Profile::Core => wgl::CONTEXT_CORE_PROFILE_BIT_ARB,
+                    Profile::Compatibility => wgl::CONTEXT_COMPATIBILITY_PROFILE_BIT_ARB,
+                });
+            }
+            let mut flags = 0;
+            if desktop && config.is_forward_compatible() {
+                flags |= wgl::CONTEXT_FORWARD_COMPATIBLE_BIT_ARB;
+            }
+            if config.is_debug() {
+                flags |= wgl::CONTEXT_DEBUG_BIT_ARB;
+            }
+            if robustness {
+                flags |= wgl::CONTEXT_ROBUST_ACCESS_BIT_ARB;
+                attribs.push(wgl::CONTEXT_RESET_NOTIFICATION_STRATEGY_ARB);
+                attribs.push(
+                    if config.get_robustness() == Robustness::NoResetNotification {
+                        wgl::NO_RESET_NOTIFICATION_ARB
+                    } else {
+                        wgl::LOSE_CONTEXT_ON_RESET_ARB
+                    },
                 );
+            }
+            if flags != 0 {
+                attribs.push(wgl::CONTEXT_FLAGS_ARB);
+                attribs.push(flags);
+            }
+            if no_error {
+                attribs.push(wgl::CONTEXT_OPENGL_NO_ERROR_ARB);
+                attribs.push(TRUE);
+            }
+            attribs.push(0);
+            let render = create(self.device, share, attribs.as_ptr());
+            if !render.is_null() && FALSE != unsafe { wglMakeCurrent(self.device, render) } {
                 #[cfg(feature = "verbose-log")]
                 log_i!(
-                    "Surface with OpenGL: {}, depth: {}, samples: {}",
-                    c[0],
-                    c[1],
-                    c[2]
+                    "WGL context with OpenGL{} {}.{} created",
+                    if desktop { "" } else { " ES" },
+                    major,
+                    minor
                 );
-                break;
+                self.render = render;
+                set_current_on_thread(render as usize);
+                return Ok((major, minor));
+            }
+            attempts.push(ContextAttempt {
+                major: major as i32,
+                minor: minor as i32,
+                code: unsafe { GetLastError() } as i32,
+            });
+            if !render.is_null() {
+                unsafe { wglDeleteContext(render) };
             }
         }
-        if surface.is_null() {
-            #[cfg(feature = "verbose-log")]
-            log_i!("Can not create EGL Surface.");
+        #[cfg(feature = "verbose-log")]
+        log_i!("Can not create WGL Context.");
+        Err(GlError::ContextCreation(attempts))
+    }
+
+    // The new context is left current on the calling thread.
+    #[cfg(target_os = "windows")]
+    pub(crate) fn create_shared(&self) -> Result<Self, GlError> {
+        // Contexts may share a device context as long as they are current on different threads.
+        let device = unsafe { GetDC(self.hwnd) };
+        if device.is_null() {
+            return Err(GlError::DeviceContext);
+        }
+        let mut context = Self {
+            window: None,
+            hwnd: self.hwnd,
+            device,
+            render: null_mut(),
+            wgl: self.wgl.clone(),
+            config: self.config.clone(),
+        };
+        context.create_context(&self.config, self.render)?;
+        Ok(context)
+    }
+
+    // A device context belongs to the thread that got it, so a context moving to another thread
+    // gives its device back first and gets a new one on the thread it moved to.
+    #[cfg(target_os = "windows")]
+    pub(crate) fn release_device(&mut self) {
+        if !self.device.is_null() {
+            unsafe { ReleaseDC(self.hwnd, self.device) };
+            self.device = null_mut();
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    pub(crate) fn acquire_device(&mut self) -> Result<(), GlError> {
+        if self.device.is_null() {
+            self.device = unsafe { GetDC(self.hwnd) };
+            if self.device.is_null() {
+                return Err(GlError::DeviceContext);
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(target_os = "windows")]
+    pub(crate) fn make_current(&self) -> Result<(), GlError> {
+        if FALSE == unsafe { wglMakeCurrent(self.device, self.render) } {
+            return Err(GlError::MakeCurrent(unsafe { GetLastError() } as i32));
+        }
+        set_current_on_thread(self.render as usize);
+        Ok(())
+    }
+
+    #[cfg(target_os = "windows")]
+    pub(crate) fn release_current(&self) {
+        unsafe { wglMakeCurrent(null_mut(), null_mut()) };
+        set_current_on_thread(0);
+    }
+
+    #[cfg(target_os = "windows")]
+    pub(crate) fn swap(&self) -> SwapStatus {
+        if FALSE != unsafe { SwapBuffers(self.device) } {
+            return SwapStatus::Ok;
+        }
+        log_e!("Context swap failed with error 0x{:X}.", unsafe {
+            GetLastError()
+        });
+        SwapStatus::SurfaceLost
+    }
+
+    #[cfg(target_os = "windows")]
+    pub(crate) fn get_surface_size(&self) -> Option<(u32, u32)> {
+        let mut rect: RECT = unsafe { zeroed() };
+        if FALSE == unsafe { GetClientRect(self.hwnd, &mut rect) } {
             return None;
         }
-        const CONTEXT_ATTRIBS: [[egl::EGLint; 5]; 4] = [
-            [
-                egl::CONTEXT_MAJOR_VERSION,
-                3,
-                egl::CONTEXT_MINOR_VERSION,
-                2,
-                egl::NONE,
-            ],
-            [
-                egl::CONTEXT_MAJOR_VERSION,
-                3,
-                egl::CONTEXT_MINOR_VERSION,
-                1,
-                egl::NONE,
-            ],
-            [
-                egl::CONTEXT_MAJOR_VERSION,
-                3,
-                egl::CONTEXT_MINOR_VERSION,
-                0,
-                egl::NONE,
-            ],
-            [
-                egl::CONTEXT_MAJOR_VERSION,
-                2,
-                egl::CONTEXT_MINOR_VERSION,
-                0,
-                egl::NONE,
-            ],
-        ];
+        Some((
+            (rect.right - rect.left) as u32,
+            (rect.bottom - rect.top) as u32,
+        ))
+    }
+
+    #[cfg(target_os = "windows")]
+    pub(crate) fn set_swap_interval(&self, interval: i32) -> Option<i32> {
+        let swap_interval = self.wgl.swap_interval?;
+        let interval = if interval < 0 && !self.wgl.has_extension("WGL_EXT_swap_control_tear") {
+            -interval
+        } else {
+            interval
+        };
+        if FALSE == swap_interval(interval) {
+            log_e!(
+                "Setting WGL swap interval failed with error 0x{:X}.",
+                unsafe { GetLastError() }
+            );
+            return None;
+        }
+        self.get_swap_interval().or(Some(interval))
+    }
+
+    #[cfg(target_os = "windows")]
+    pub(crate) fn get_swap_interval(&self) -> Option<i32> {
+        self.wgl.get_swap_interval.map(|f| f())
+    }
+
+    // A window keeps its device context across minimizing, so there is nothing to recreate.
+    #[cfg(target_os = "windows")]
+    pub(crate) fn suspend(&self) {}
+
+    #[cfg(target_os = "windows")]
+    pub(crate) fn resume(&self, _window: &Window) -> Result<SwapStatus, GlError> {
+        Ok(SwapStatus::Ok)
+    }
+
+    #[cfg(target_os = "windows")]
+    pub(crate) fn get_function<T>(&self, s: &str) -> Option<T> {
+        wgl::get_function(s)
+    }
 
-        let mut context = null_mut();
+    #[cfg(target_os = "linux")]
+    pub fn new(window: Arc<Window>, config: &ContextConfig) -> Result<Self, GlError> {
+        #[cfg(feature = "egl")]
+        return Self::new_egl(Some(window), Platform::Default, config, 0, 0);
+        #[cfg(not(feature = "egl"))]
+        {
+            let glx = GlxContext::current(&window);
+            set_current_on_thread(glx.as_ref().map_or(0, |glx| glx.context as usize));
+            let config = match &glx {
+                Some(glx) => glx.get_config(config)?,
+                None => {
+                    log_e!("The window has no current GLX context.");
+                    return Err(GlError::ContextCreation(Vec::new()));
+                }
+            };
+            #[cfg(feature = "verbose-log")]
+            log_i!("GLX context of the window is used, its config is read back.");
+            Ok(Self {
+                glx,
+                window: Some(window),
+                egl: None,
+                config,
+            })
+        }
+    }
 
-        for attribs in &CONTEXT_ATTRIBS {
-            context = (egl_lib.create_context)(display, config, null_mut(), attribs.as_ptr());
-            if context.is_null() {
+    // No native handles at all, so it is current wherever the mock loader is called.
+    #[cfg(all(
+        test,
+        feature = "gl-mock",
+        any(target_os = "android", target_os = "linux")
+    ))]
+    pub(crate) fn new_mock() -> Self {
+        Self {
+            window: None,
+            egl: None,
+            #[cfg(target_os = "linux")]
+            glx: None,
+            config: ContextConfig::default(),
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn new_headless(width: u32, height: u32, config: &ContextConfig) -> Result<Self, GlError> {
+        Self::new_egl(None, Platform::Surfaceless, config, width, height)
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn new_egl(
+        window: Option<Arc<Window>>,
+        platform: Platform,
+        config: &ContextConfig,
+        width: u32,
+        height: u32,
+    ) -> Result<Self, GlError> {
+        let egl_lib = egl::Egl::new()?;
+        let display = match platform {
+            Platform::Default => (egl_lib.get_display)(egl::DEFAULT_DISPLAY),
+            Platform::X11 { display, .. } => {
+                egl_lib.get_display_for(egl::PLATFORM_X11_KHR, display)
+            }
+            Platform::Wayland { display, .. } => {
+                egl_lib.get_display_for(egl::PLATFORM_WAYLAND_KHR, display)
+            }
+            Platform::Gbm { device, .. } => egl_lib.get_display_for(egl::PLATFORM_GBM_KHR, device),
+            Platform::Surfaceless => {
+                let display = if egl_lib
+                    .has_extension(egl::NO_DISPLAY, "EGL_MESA_platform_surfaceless")
+                {
+                    egl_lib.get_display_for(egl::PLATFORM_SURFACELESS_MESA, egl::DEFAULT_DISPLAY)
+                } else {
+                    egl::NO_DISPLAY
+                };
+                if display.is_null() {
+                    (egl_lib.get_display)(egl::DEFAULT_DISPLAY)
+                } else {
+                    display
+                }
+            }
+        };
+        let native_window: Option<egl::EGLNativeWindowType> = match platform {
+            Platform::Default => window
+                .as_ref()
+                .map(|w| unsafe { transmute_copy(&w.get_window()) }),
+            Platform::X11 { window, .. } => Some(window),
+            Platform::Wayland { window, .. } => Some(window as egl::EGLNativeWindowType),
+            Platform::Gbm { surface, .. } => Some(surface as egl::EGLNativeWindowType),
+            Platform::Surfaceless => None,
+        };
+        let mut state = EglState::new(egl_lib, display)?;
+        state.bind_api(config.get_api())?;
+        let surfaceless = state
+            .lib
+            .has_extension(state.display, "EGL_KHR_surfaceless_context");
+        let surface_types: &[egl::EGLint] = if native_window.is_some() {
+            &[egl::WINDOW_BIT]
+        } else if surfaceless {
+            &[egl::PBUFFER_BIT, 0]
+        } else {
+            &[egl::PBUFFER_BIT]
+        };
+        for &surface_type in surface_types {
+            if !state.choose_config(config, surface_type) {
                 continue;
             }
-            if egl::TRUE == (egl_lib.make_current)(display, surface, surface, context) {
-                #[cfg(feature = "verbose-log")]
-                log_i!(
-                    "EGL context with OpenGL ES {}.{} created",
-                    attribs[1],
-                    attribs[3]
+            if let Some(native_window) = native_window {
+                let attribs = state.get_surface_attribs(config);
+                state.surface = (state.lib.create_window_surface)(
+                    state.display,
+                    state.config,
+                    native_window,
+                    attribs.as_ptr(),
                 );
-                break;
-            } else {
-                context = null_mut();
+                if state.surface.is_null() {
+                    return Err(GlError::SurfaceCreation((state.lib.get_error)()));
+                }
+            } else if surface_type == egl::PBUFFER_BIT {
+                // Sizes past the EGL range are left for EGL to reject.
+                let size = |s: u32| s.min(i32::MAX as u32) as i32;
+                let mut attribs = vec![egl::WIDTH, size(width), egl::HEIGHT, size(height)];
+                attribs.append(&mut state.get_surface_attribs(config));
+                state.surface = (state.lib.create_pbuffer_surface)(
+                    state.display,
+                    state.config,
+                    attribs.as_ptr(),
+                );
+                if state.surface.is_null() && !surfaceless {
+                    return Err(GlError::SurfaceCreation((state.lib.get_error)()));
+                }
             }
+            break;
         }
-
-        if context.is_null() {
+        if state.config.is_null() {
             #[cfg(feature = "verbose-log")]
-            log_i!("Can not create EGL Context.");
-            return None;
+            log_i!("Can not find a matching EGL config.");
+            return Err(GlError::NoMatchingConfig);
         }
-
-        Some(Self {
+        let config = state.create_context(config, egl::NO_CONTEXT)?;
+        #[cfg(feature = "verbose-log")]
+        log_i!("EGL context created on {:?} platform.", platform);
+        Ok(Self {
             window,
-            egl_lib,
-            display,
+            egl: Some(RefCell::new(state)),
+            glx: None,
             config,
-            surface,
-            context,
         })
     }
 
-    // void gearoenix::system::GlContext::suspend() noexcept
-    // {
-    //     if (surface != EGL_NO_SURFACE) {
-    //         eglDestroySurface(display, surface);
-    //         surface = EGL_NO_SURFACE;
-    //     }
-    // }
-    // void gearoenix::system::GlContext::resume(ANativeWindow* const window) noexcept
-    // {
-    //     if (!egl_context_initialized) {
-    //         init(window);
-    //         return;
-    //     }
-    //     const int original_widhth = screen_width;
-    //     const int original_height = screen_height;
-    //     this->window = window;
-    //     surface = eglCreateWindowSurface(display, config, window, nullptr);
-    //     eglQuerySurface(display, surface, EGL_WIDTH, &screen_width);
-    //     eglQuerySurface(display, surface, EGL_HEIGHT, &screen_height);
-    //     if (screen_width != original_widhth || screen_height != original_height)
-    //         GXLOGD("Screen resized");
-    //     if (eglMakeCurrent(display, surface, surface, context) == EGL_TRUE)
-    //         return;
-    //     const EGLint err = eglGetError();
-    //     GXLOGD("Unable to eglMakeCurrent " << err);
-    //     if (err == EGL_CONTEXT_LOST) {
-    //         GXLOGD("Re-creating egl context");
-    //         init_egl_context();
-    //     } else {
-    //         terminate();
-    //         init_egl_surface();
-    //         init_egl_context();
-    //     }
-    // }
+    #[cfg(target_os = "linux")]
+    pub fn swap(&self) -> SwapStatus {
+        match (&self.egl, &self.window) {
+            (Some(egl), _) => egl.borrow().swap(),
+            (None, Some(window)) => {
+                window.swap();
+                SwapStatus::Ok
+            }
+            (None, None) => SwapStatus::Ok,
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn get_function<T>(&self, s: &str) -> Option<T> {
+        match (&self.egl, &self.glx, &self.window) {
+            (Some(egl), _, _) => egl.borrow().get_function(s),
+            (None, Some(glx), _) => glx.get_function(s),
+            (None, None, Some(window)) => window.get_gl_function(s),
+            (None, None, None) => None,
+        }
+    }
+
+    // The unversioned names only ship with the -dev packages, so the sonames are tried after them.
+    #[cfg(target_os = "linux")]
+    pub fn get_library_names(&self) -> &'static [&'static str] {
+        match &self.egl {
+            Some(egl) if !egl.borrow().desktop => &["libGLESv2.so", "libGLESv2.so.2"],
+            _ => &["libGL.so", "libGL.so.1"],
+        }
+    }
 
     #[cfg(target_os = "android")]
-    pub fn swap(&self) {
-        if egl::TRUE != (self.egl_lib.swap_buffers)(self.display, self.surface) {
-            log_f!("EGL context is not valid any more. There is a bug somewhere that does not initialize the context in the correct way.");
-            //     if (b == 0) {
-            //         const EGLint err = eglGetError();
-            //         if (err == EGL_BAD_SURFACE) {
-            //             init_egl_surface();
-            //             return State::RUNNING;
-            //         } else if (err == EGL_CONTEXT_LOST || err == EGL_BAD_CONTEXT) {
-            //             terminate();
-            //             return State::TERMINATED;
-            //         }
-            //         GXLOGE("Unhandled error " << err)
+    pub fn new(window: Arc<Window>, config: &ContextConfig) -> Result<Self, GlError> {
+        let egl_lib = egl::Egl::new()?;
+        let display = (egl_lib.get_display)(egl::DEFAULT_DISPLAY);
+        let mut state = EglState::new(egl_lib, display)?;
+        state.bind_api(config.get_api())?;
+        if !state.choose_config(config, egl::WINDOW_BIT) {
+            #[cfg(feature = "verbose-log")]
+            log_i!("Can not find a matching EGL config.");
+            return Err(GlError::NoMatchingConfig);
+        }
+        let attribs = state.get_surface_attribs(config);
+        state.surface = (state.lib.create_window_surface)(
+            state.display,
+            state.config,
+            unsafe { transmute_copy(&window.get_window()) },
+            attribs.as_ptr(),
+        );
+        if state.surface.is_null() {
+            #[cfg(feature = "verbose-log")]
+            log_i!("Can not create EGL Surface.");
+            return Err(GlError::SurfaceCreation((state.lib.get_error)()));
+        }
+        let config = state.create_context(config, egl::NO_CONTEXT)?;
+        #[cfg(feature = "verbose-log")]
+        log_i!(
+            "Surface with color: {:?}, depth: {}, samples: {}",
+            config.get_color_bits(),
+            config.get_depth_bits(),
+            config.get_samples()
+        );
+        Ok(Self {
+            window: Some(window),
+            egl: Some(RefCell::new(state)),
+            config,
+        })
+    }
+
+    pub(crate) fn get_config(&self) -> &ContextConfig {
+        &self.config
+    }
+
+    // The native handle, it changes when a lost context is recreated.
+    #[cfg(all(feature = "gl-check", debug_assertions))]
+    pub(crate) fn get_id(&self) -> usize {
+        #[cfg(target_os = "windows")]
+        return self.render as usize;
+        #[cfg(any(target_os = "android", target_os = "linux"))]
+        {
+            if let Some(egl) = &self.egl {
+                return egl.borrow().context as usize;
+            }
+            #[cfg(target_os = "linux")]
+            {
+                if let Some(glx) = &self.glx {
+                    return glx.context as usize;
+                }
+            }
+            0
+        }
+    }
+
+    // The new context is left current on the calling thread.
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    pub(crate) fn create_shared(&self) -> Result<Self, GlError> {
+        if let Some(egl) = &self.egl {
+            let state = egl.borrow().create_shared(&self.config)?;
+            state.make_current()?;
+            return Ok(Self {
+                window: None,
+                egl: Some(RefCell::new(state)),
+                #[cfg(target_os = "linux")]
+                glx: None,
+                config: self.config.clone(),
+            });
+        }
+        #[cfg(target_os = "linux")]
+        {
+            if let Some(glx) = &self.glx {
+                let glx = glx.create_shared(&self.config)?;
+                glx.make_current()?;
+                return Ok(Self {
+                    window: None,
+                    egl: None,
+                    glx: Some(glx),
+                    config: self.config.clone(),
+                });
+            }
+        }
+        Err(GlError::ContextCreation(Vec::new()))
+    }
+
+    // EGL and GLX handles are not tied to the thread that got them.
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    pub(crate) fn release_device(&mut self) {}
+
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    pub(crate) fn acquire_device(&mut self) -> Result<(), GlError> {
+        Ok(())
+    }
+
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    pub(crate) fn make_current(&self) -> Result<(), GlError> {
+        if let Some(egl) = &self.egl {
+            return egl.borrow().make_current();
+        }
+        #[cfg(target_os = "linux")]
+        {
+            if let Some(glx) = &self.glx {
+                return glx.make_current();
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    pub(crate) fn release_current(&self) {
+        if let Some(egl) = &self.egl {
+            egl.borrow().release_current();
+        }
+        #[cfg(target_os = "linux")]
+        {
+            if let Some(glx) = &self.glx {
+                glx.release_current();
+            }
+        }
+    }
+
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    pub(crate) fn suspend(&self) {
+        if let Some(egl) = &self.egl {
+            egl.borrow_mut().suspend();
+        }
+    }
+
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    pub(crate) fn resume(&self, window: &Window) -> Result<SwapStatus, GlError> {
+        match &self.egl {
+            Some(egl) => egl.borrow_mut().resume(
+                unsafe { transmute_copy(&window.get_window()) },
+                &self.config,
+            ),
+            None => Ok(SwapStatus::Ok),
         }
     }
 
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    pub(crate) fn get_surface_size(&self) -> Option<(u32, u32)> {
+        self.egl
+            .as_ref()
+            .and_then(|egl| egl.borrow().get_surface_size())
+    }
+
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    pub(crate) fn set_swap_interval(&self, interval: i32) -> Option<i32> {
+        match (&self.egl, &self.window) {
+            (Some(egl), _) => egl.borrow_mut().set_swap_interval(interval),
+            #[cfg(target_os = "linux")]
+            (None, Some(window)) => Glx::new(window)?.set_swap_interval(interval),
+            _ => None,
+        }
+    }
+
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    pub(crate) fn get_swap_interval(&self) -> Option<i32> {
+        match (&self.egl, &self.window) {
+            (Some(egl), _) => egl.borrow().get_swap_interval(),
+            #[cfg(target_os = "linux")]
+            (None, Some(window)) => Glx::new(window)?.get_swap_interval(),
+            _ => None,
+        }
+    }
+
+    #[cfg(target_os = "android")]
+    pub fn swap(&self) -> SwapStatus {
+        self.egl
+            .as_ref()
+            .map_or(SwapStatus::Ok, |egl| egl.borrow().swap())
+    }
+
     #[cfg(target_os = "android")]
     pub fn get_function<T>(&self, s: &str) -> Option<T> {
-        let cs = CString::new(s).unwrap();
-        if let Some(f) = (self.egl_lib.get_proc_address)(cs.as_ptr()) {
-            Some(unsafe { transmute_copy(&f) })
-        } else {
-            None
-        }
+        self.egl
+            .as_ref()
+            .and_then(|egl| egl.borrow().get_function(s))
     }
 }
 
 impl Drop for Context {
     fn drop(&mut self) {
         #[cfg(target_os = "windows")]
-        {
-            use winapi::shared::minwindef::FALSE;
-            use winapi::um::wingdi::wglDeleteContext;
-            if FALSE == unsafe { wglDeleteContext(self.render) } {
+        unsafe {
+            if wglGetCurrentContext() == self.render {
+                self.release_current();
+            }
+            if !self.render.is_null() && FALSE == wglDeleteContext(self.render) {
                 log_f!("Failed to destroy render context.");
             }
-        }
-        #[cfg(target_os = "android")]
-        {
-            if !self.display.is_null() {
-                (self.egl_lib.make_current)(self.display, null_mut(), null_mut(), null_mut());
-                if !self.context.is_null() {
-                    if egl::TRUE != (self.egl_lib.destroy_context)(self.display, self.context) {
-                        log_f!("Failed to terminate EGL context.");
-                    }
-                }
-                if !self.surface.is_null() {
-                    if egl::TRUE != (self.egl_lib.destroy_surface)(self.display, self.surface) {
-                        log_f!("Failed to terminate EGL surface.");
-                    }
-                }
-                if egl::TRUE != (self.egl_lib.terminate)(self.display) {
-                    log_f!("Failed to terminate EGL.");
-                }
+            if !self.device.is_null() {
+                ReleaseDC(self.hwnd, self.device);
             }
         }
     }
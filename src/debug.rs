@@ -0,0 +1,241 @@
+use {
+    super::{
+        config::Api,
+        constants::{
+            Capability, DebugSeverity, DebugSource, DebugType, ObjectIdentifier, DONT_CARE, VERSION,
+        },
+        info::parse_version,
+        loader::Loader,
+        types::{Boolean, Enumerated, SizeI, UInt},
+    },
+    crate::window::log::{log_e, log_i},
+    std::{
+        cell::Cell,
+        convert::TryFrom,
+        ffi::CStr,
+        os::raw::{c_char, c_void},
+        process::abort,
+        ptr::null,
+        slice::from_raw_parts,
+        sync::Arc,
+    },
+};
+
+// The driver reads the state through the callback's user param, so it is only changed through a
+// Cell behind a shared reference.
+struct DebugState {
+    fatal_errors: Cell<bool>,
+}
+
+extern "C" fn callback(
+    source: Enumerated,
+    message_type: Enumerated,
+    id: UInt,
+    severity: Enumerated,
+    length: SizeI,
+    message: *const c_char,
+    user_param: *const c_void,
+) {
+    let state = unsafe { &*(user_param as *const DebugState) };
+    let message = if message.is_null() {
+        "".into()
+    } else {
+        String::from_utf8_lossy(unsafe { from_raw_parts(message as *const u8, length as usize) })
+    };
+    let source = DebugSource::try_from(source);
+    let message_type = DebugType::try_from(message_type);
+    let severity = DebugSeverity::try_from(severity);
+    // A panic can not unwind out of this extern "C" fn, so a fatal error aborts instead.
+    if state.fatal_errors.get() && message_type == Ok(DebugType::Error) {
+        log_e!(
+            "OpenGL {:?} {:?} {} {:?}: {}",
+            source,
+            message_type,
+            id,
+            severity,
+            message
+        );
+        abort();
+    } else if severity == Ok(DebugSeverity::High) || message_type == Ok(DebugType::Error) {
+        log_e!(
+            "OpenGL {:?} {:?} {} {:?}: {}",
+            source,
+            message_type,
+            id,
+            severity,
+            message
+        );
+    } else {
+        log_i!(
+            "OpenGL {:?} {:?} {} {:?}: {}",
+            source,
+            message_type,
+            id,
+            severity,
+            message
+        );
+    }
+}
+
+pub struct DebugOutput {
+    loader: Arc<Loader>,
+    state: Box<DebugState>,
+}
+
+impl DebugOutput {
+    pub fn new(loader: &Arc<Loader>) -> Option<Self> {
+        let version = loader.get_string(VERSION);
+        let core = !version.is_null()
+            && match parse_version(
+                &unsafe { CStr::from_ptr(version as *const c_char) }.to_string_lossy(),
+            ) {
+                (Api::Gl, v) => v >= (4, 3),
+                (Api::Gles, v) => v >= (3, 2),
+            };
+        if !core && !loader.has_extension("GL_KHR_debug") {
+            return None;
+        }
+        loader.debug_message_callback?;
+        loader.debug_message_control?;
+        let state = Box::new(DebugState {
+            fatal_errors: Cell::new(false),
+        });
+        loader.enable(Capability::DebugOutput.into());
+        loader.enable(Capability::DebugOutputSynchronous.into());
+        loader.debug_message_callback(
+            Some(callback),
+            &*state as *const DebugState as *const c_void,
+        );
+        Some(Self {
+            loader: loader.clone(),
+            state,
+        })
+    }
+
+    // The output is synchronous, so a fatal error aborts the process inside the failing call.
+    pub fn set_fatal_errors(&self, fatal_errors: bool) {
+        self.state.fatal_errors.set(fatal_errors);
+    }
+
+    pub fn control(
+        &self,
+        source: Option<DebugSource>,
+        message_type: Option<DebugType>,
+        severity: Option<DebugSeverity>,
+        enabled: bool,
+    ) {
+        self.loader.debug_message_control(
+            source.map_or(DONT_CARE, |s| s.into()),
+            message_type.map_or(DONT_CARE, |t| t.into()),
+            severity.map_or(DONT_CARE, |s| s.into()),
+            0,
+            null(),
+            enabled as Boolean,
+        );
+    }
+
+    pub fn control_ids(
+        &self,
+        source: DebugSource,
+        message_type: DebugType,
+        ids: &[UInt],
+        enabled: bool,
+    ) {
+        self.loader.debug_message_control(
+            source.into(),
+            message_type.into(),
+            DONT_CARE,
+            ids.len() as SizeI,
+            ids.as_ptr(),
+            enabled as Boolean,
+        );
+    }
+
+    pub fn insert(
+        &self,
+        message_type: DebugType,
+        id: UInt,
+        severity: DebugSeverity,
+        message: &str,
+    ) {
+        if self.loader.debug_message_insert.is_some() {
+            self.loader.debug_message_insert(
+                DebugSource::Application.into(),
+                message_type.into(),
+                id,
+                severity.into(),
+                message.len() as SizeI,
+                message.as_ptr() as *const c_char,
+            );
+        }
+    }
+
+    pub fn set_label(&self, identifier: ObjectIdentifier, name: UInt, label: &str) {
+        if self.loader.object_label.is_some() {
+            self.loader.object_label(
+                identifier.into(),
+                name,
+                label.len() as SizeI,
+                label.as_ptr() as *const c_char,
+            );
+        }
+    }
+}
+
+impl Drop for DebugOutput {
+    fn drop(&mut self) {
+        if self.loader.debug_message_callback.is_some() {
+            self.loader.debug_message_callback(None, null());
+        }
+        self.loader
+            .disable(Capability::DebugOutputSynchronous.into());
+        self.loader.disable(Capability::DebugOutput.into());
+    }
+}
+
+#[cfg(all(test, feature = "gl-mock"))]
+mod tests {
+    use {super::*, crate::mock};
+
+    #[test]
+    fn installs_and_removes_the_callback() {
+        let loader = mock::new_loader();
+        let output = DebugOutput::new(&loader).unwrap();
+        let state = &*output.state as *const DebugState as u64;
+        let enabled: Vec<u64> = mock::get_calls_of("glEnable")
+            .iter()
+            .map(|c| c.args[0])
+            .collect();
+        let debug_output: Enumerated = Capability::DebugOutput.into();
+        let synchronous: Enumerated = Capability::DebugOutputSynchronous.into();
+        assert_eq!(enabled, [debug_output as u64, synchronous as u64]);
+        let installed = mock::get_calls_of("glDebugMessageCallback");
+        assert_eq!(installed.len(), 1);
+        assert_ne!(installed[0].args[0], 0);
+        assert_eq!(installed[0].args[1], state);
+
+        mock::clear_calls();
+        drop(output);
+        let removed = mock::get_calls_of("glDebugMessageCallback");
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].args, [0, 0]);
+        let disabled: Vec<u64> = mock::get_calls_of("glDisable")
+            .iter()
+            .map(|c| c.args[0])
+            .collect();
+        assert_eq!(disabled, [synchronous as u64, debug_output as u64]);
+    }
+
+    #[test]
+    fn fatal_errors_are_seen_through_the_user_param() {
+        let loader = mock::new_loader();
+        let output = DebugOutput::new(&loader).unwrap();
+        let user_param = mock::get_calls_of("glDebugMessageCallback")[0].args[1];
+        let state = unsafe { &*(user_param as usize as *const DebugState) };
+        assert!(!state.fatal_errors.get());
+        output.set_fatal_errors(true);
+        assert!(state.fatal_errors.get());
+        output.set_fatal_errors(false);
+        assert!(!state.fatal_errors.get());
+    }
+}
@@ -1,11 +1,21 @@
 use {
+    error::GlError,
     library_loader::Linker,
-    log::unwrap_f,
-    std::os::raw::{c_char, c_uint, c_ulong, c_void},
+    std::{
+        ffi::CStr,
+        os::raw::{c_char, c_uint, c_ulong, c_void},
+        ptr::null_mut,
+        sync::Mutex,
+    },
 };
+#[cfg(target_os = "linux")]
+use std::ptr::null;
 
 pub type EGLint = i32;
 pub type EGLBoolean = c_uint;
+pub type EGLenum = c_uint;
+#[cfg(target_os = "linux")]
+pub type EGLAttrib = isize;
 
 pub type EGLNativeDisplayType = *mut c_void;
 pub type EGLNativeWindowType = c_ulong;
@@ -15,8 +25,15 @@ pub type EGLSurface = *mut c_void;
 pub type EGLContext = *mut c_void;
 
 pub const TRUE: EGLBoolean = 1;
-pub const FALSE: EGLBoolean = 0;
 pub const DEFAULT_DISPLAY: EGLNativeDisplayType = 0 as EGLNativeDisplayType;
+// Only the desktop paths pick a platform or go headless.
+#[cfg(target_os = "linux")]
+pub const NO_DISPLAY: EGLDisplay = 0 as EGLDisplay;
+pub const NO_SURFACE: EGLSurface = 0 as EGLSurface;
+pub const NO_CONTEXT: EGLContext = 0 as EGLContext;
+#[cfg(target_os = "linux")]
+pub const PBUFFER_BIT: EGLint = 1;
+pub const OPENGL_BIT: EGLint = 8;
 pub const OPENGL_ES2_BIT: EGLint = 4;
 pub const OPENGL_ES3_BIT: EGLint = 64;
 pub const RENDERABLE_TYPE: EGLint = 12352;
@@ -30,9 +47,42 @@ pub const DEPTH_SIZE: EGLint = 12325;
 pub const STENCIL_SIZE: EGLint = 12326;
 pub const SAMPLE_BUFFERS: EGLint = 12338;
 pub const SAMPLES: EGLint = 12337;
+pub const MIN_SWAP_INTERVAL: EGLint = 12347;
+pub const MAX_SWAP_INTERVAL: EGLint = 12348;
 pub const CONTEXT_MAJOR_VERSION: EGLint = 12440;
 pub const CONTEXT_MINOR_VERSION: EGLint = 12539;
+pub const CONTEXT_OPENGL_PROFILE_MASK: EGLint = 12541;
+pub const CONTEXT_OPENGL_CORE_PROFILE_BIT: EGLint = 1;
+pub const CONTEXT_OPENGL_COMPATIBILITY_PROFILE_BIT: EGLint = 2;
+pub const CONTEXT_OPENGL_DEBUG: EGLint = 12720;
+pub const CONTEXT_OPENGL_FORWARD_COMPATIBLE: EGLint = 12721;
+pub const CONTEXT_OPENGL_ROBUST_ACCESS: EGLint = 12722;
+pub const CONTEXT_OPENGL_NO_ERROR: EGLint = 12723;
+pub const CONTEXT_OPENGL_RESET_NOTIFICATION_STRATEGY: EGLint = 12733;
+pub const NO_RESET_NOTIFICATION: EGLint = 12734;
+pub const LOSE_CONTEXT_ON_RESET: EGLint = 12735;
+pub const GL_COLORSPACE: EGLint = 12445;
+pub const GL_COLORSPACE_SRGB: EGLint = 12425;
 pub const NONE: EGLint = 12344;
+pub const EXTENSIONS: EGLint = 12373;
+pub const WIDTH: EGLint = 12375;
+pub const HEIGHT: EGLint = 12374;
+pub const OPENGL_ES_API: EGLenum = 12448;
+pub const OPENGL_API: EGLenum = 12450;
+#[cfg(target_os = "linux")]
+pub const PLATFORM_X11_KHR: EGLenum = 12757;
+#[cfg(target_os = "linux")]
+pub const PLATFORM_GBM_KHR: EGLenum = 12759;
+#[cfg(target_os = "linux")]
+pub const PLATFORM_WAYLAND_KHR: EGLenum = 12760;
+#[cfg(target_os = "linux")]
+pub const PLATFORM_SURFACELESS_MESA: EGLenum = 12765;
+pub const BAD_CONTEXT: EGLint = 12294;
+pub const BAD_NATIVE_WINDOW: EGLint = 12299;
+pub const BAD_SURFACE: EGLint = 12301;
+pub const CONTEXT_LOST: EGLint = 12302;
+
+static DISPLAYS: Mutex<Vec<(usize, usize)>> = Mutex::new(Vec::new());
 
 pub struct Egl {
     pub get_display: extern "C" fn(display_id: EGLNativeDisplayType) -> EGLDisplay,
@@ -63,43 +113,142 @@ pub struct Egl {
         read: EGLSurface,
         ctx: EGLContext,
     ) -> EGLBoolean,
+    pub get_current_context: extern "C" fn() -> EGLContext,
     pub destroy_context: extern "C" fn(dpy: EGLDisplay, ctx: EGLContext) -> EGLBoolean,
     pub destroy_surface: extern "C" fn(dpy: EGLDisplay, surface: EGLSurface) -> EGLBoolean,
     pub terminate: extern "C" fn(dpy: EGLDisplay) -> EGLBoolean,
     pub get_proc_address: extern "C" fn(procname: *const c_char) -> Option<extern "C" fn()>,
     pub swap_buffers: extern "C" fn(dpy: EGLDisplay, surface: EGLSurface) -> EGLBoolean,
+    pub swap_interval: extern "C" fn(dpy: EGLDisplay, interval: EGLint) -> EGLBoolean,
+    pub get_error: extern "C" fn() -> EGLint,
+    pub query_string: extern "C" fn(dpy: EGLDisplay, name: EGLint) -> *const c_char,
+    pub bind_api: extern "C" fn(api: EGLenum) -> EGLBoolean,
+    pub get_config_attrib: extern "C" fn(
+        dpy: EGLDisplay,
+        config: EGLConfig,
+        attribute: EGLint,
+        value: *mut EGLint,
+    ) -> EGLBoolean,
+    pub create_pbuffer_surface:
+        extern "C" fn(dpy: EGLDisplay, config: EGLConfig, attrib_list: *const EGLint) -> EGLSurface,
+    pub query_surface: extern "C" fn(
+        dpy: EGLDisplay,
+        surface: EGLSurface,
+        attribute: EGLint,
+        value: *mut EGLint,
+    ) -> EGLBoolean,
+    #[cfg(target_os = "linux")]
+    pub get_platform_display: Option<
+        extern "C" fn(
+            platform: EGLenum,
+            native_display: *mut c_void,
+            attrib_list: *const EGLAttrib,
+        ) -> EGLDisplay,
+    >,
+    #[cfg(target_os = "linux")]
+    pub get_platform_display_ext: Option<
+        extern "C" fn(
+            platform: EGLenum,
+            native_display: *mut c_void,
+            attrib_list: *const EGLint,
+        ) -> EGLDisplay,
+    >,
     _lib: Linker,
 }
 
 impl Egl {
-    pub fn new() -> Option<Self> {
-        let _lib = if let Some(l) = Linker::new("libEGL.so") {
+    pub fn new() -> Result<Self, GlError> {
+        // Runtime-only installs have just the soname.
+        let names = ["libEGL.so", "libEGL.so.1"];
+        let _lib = if let Some(l) = names.iter().find_map(|n| Linker::new(n)) {
             l
         } else {
-            return None;
+            return Err(GlError::LibraryNotFound("libEGL.so"));
         };
         macro_rules! fun {
             ($f:ident) => {
                 if let Some(f) = _lib.get_function(concat!("egl", stringify!($f))) {
                     f
                 } else {
-                    return None;
+                    return Err(GlError::FunctionNotFound(concat!("egl", stringify!($f))));
                 }
             };
         }
-        Some(Self {
+        Ok(Self {
             get_display: fun!(GetDisplay),
             initialize: fun!(Initialize),
             choose_config: fun!(ChooseConfig),
             create_window_surface: fun!(CreateWindowSurface),
             create_context: fun!(CreateContext),
             make_current: fun!(MakeCurrent),
+            get_current_context: fun!(GetCurrentContext),
             destroy_context: fun!(DestroyContext),
             destroy_surface: fun!(DestroySurface),
             terminate: fun!(Terminate),
             get_proc_address: fun!(GetProcAddress),
             swap_buffers: fun!(SwapBuffers),
+            swap_interval: fun!(SwapInterval),
+            get_error: fun!(GetError),
+            query_string: fun!(QueryString),
+            bind_api: fun!(BindAPI),
+            get_config_attrib: fun!(GetConfigAttrib),
+            create_pbuffer_surface: fun!(CreatePbufferSurface),
+            query_surface: fun!(QuerySurface),
+            #[cfg(target_os = "linux")]
+            get_platform_display: _lib.get_function("eglGetPlatformDisplay"),
+            #[cfg(target_os = "linux")]
+            get_platform_display_ext: _lib.get_function("eglGetPlatformDisplayEXT"),
             _lib,
         })
     }
+
+    pub fn initialize_display(&self, display: EGLDisplay) -> bool {
+        let mut displays = DISPLAYS.lock().unwrap();
+        if let Some(d) = displays.iter_mut().find(|d| d.0 == display as usize) {
+            d.1 += 1;
+            return true;
+        }
+        if TRUE != (self.initialize)(display, null_mut(), null_mut()) {
+            return false;
+        }
+        displays.push((display as usize, 1));
+        true
+    }
+
+    pub fn terminate_display(&self, display: EGLDisplay) -> bool {
+        let mut displays = DISPLAYS.lock().unwrap();
+        // A display we did not initialize belongs to someone else.
+        let i = match displays.iter().position(|d| d.0 == display as usize) {
+            Some(i) => i,
+            None => return true,
+        };
+        displays[i].1 -= 1;
+        if displays[i].1 > 0 {
+            return true;
+        }
+        displays.remove(i);
+        TRUE == (self.terminate)(display)
+    }
+
+    pub fn has_extension(&self, display: EGLDisplay, name: &str) -> bool {
+        let extensions = (self.query_string)(display, EXTENSIONS);
+        if extensions.is_null() {
+            return false;
+        }
+        unsafe { CStr::from_ptr(extensions) }
+            .to_string_lossy()
+            .split_whitespace()
+            .any(|e| e == name)
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn get_display_for(&self, platform: EGLenum, native_display: *mut c_void) -> EGLDisplay {
+        if let Some(f) = self.get_platform_display {
+            f(platform, native_display, null())
+        } else if let Some(f) = self.get_platform_display_ext {
+            f(platform, native_display, null())
+        } else {
+            NO_DISPLAY
+        }
+    }
 }
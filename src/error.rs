@@ -0,0 +1,112 @@
+use {
+    super::{constants::InternalFormat, objects::Attachment, types::Enumerated},
+    std::{error::Error, fmt},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContextAttempt {
+    pub major: i32,
+    pub minor: i32,
+    pub code: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwapStatus {
+    Ok,
+    SurfaceLost,
+    ContextLost,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FramebufferError {
+    Undefined,
+    IncompleteAttachment(Option<Attachment>),
+    MissingAttachment,
+    IncompleteDrawBuffer(Option<Attachment>),
+    IncompleteReadBuffer(Option<Attachment>),
+    Unsupported(Option<Attachment>),
+    IncompleteMultisample(Option<Attachment>),
+    IncompleteLayerTargets(Option<Attachment>),
+    InvalidFormat(Attachment, InternalFormat),
+    Unknown(Enumerated),
+}
+
+impl fmt::Display for FramebufferError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (reason, attachment) = match *self {
+            FramebufferError::Undefined => ("default framebuffer does not exist", None),
+            FramebufferError::IncompleteAttachment(a) => ("incomplete attachment", a),
+            FramebufferError::MissingAttachment => ("no attachment", None),
+            FramebufferError::IncompleteDrawBuffer(a) => ("draw buffer without attachment", a),
+            FramebufferError::IncompleteReadBuffer(a) => ("read buffer without attachment", a),
+            FramebufferError::Unsupported(a) => ("unsupported format combination", a),
+            FramebufferError::IncompleteMultisample(a) => ("mismatched sample counts", a),
+            FramebufferError::IncompleteLayerTargets(a) => ("mismatched layer targets", a),
+            FramebufferError::InvalidFormat(a, format) => {
+                return write!(f, "format {:?} can not be attached at {:?}", format, a)
+            }
+            FramebufferError::Unknown(status) => {
+                return write!(f, "unknown framebuffer status 0x{:X}", status)
+            }
+        };
+        match attachment {
+            Some(a) => write!(f, "{} at {:?}", reason, a),
+            None => write!(f, "{}", reason),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GlError {
+    LibraryNotFound(&'static str),
+    FunctionNotFound(&'static str),
+    DisplayNotFound,
+    EglInitialization(i32),
+    NoMatchingConfig,
+    SurfaceCreation(i32),
+    ContextCreation(Vec<ContextAttempt>),
+    MakeCurrent(i32),
+    DeviceContext,
+    PixelFormat,
+    IncompleteFramebuffer(FramebufferError),
+    InvalidName(String),
+    UnsupportedReadFormat(InternalFormat),
+    ReadbackFailed,
+}
+
+impl fmt::Display for GlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GlError::LibraryNotFound(name) => write!(f, "can not load library {}", name),
+            GlError::FunctionNotFound(name) => write!(f, "can not load function {}", name),
+            GlError::DisplayNotFound => write!(f, "can not get the default display"),
+            GlError::EglInitialization(code) => {
+                write!(f, "EGL initialization failed with error 0x{:X}", code)
+            }
+            GlError::NoMatchingConfig => write!(f, "no matching framebuffer config"),
+            GlError::SurfaceCreation(code) => {
+                write!(f, "surface creation failed with error 0x{:X}", code)
+            }
+            GlError::ContextCreation(attempts) => {
+                write!(f, "context creation failed")?;
+                for a in attempts {
+                    write!(f, ", {}.{}: 0x{:X}", a.major, a.minor, a.code)?;
+                }
+                Ok(())
+            }
+            GlError::MakeCurrent(code) => {
+                write!(f, "can not make context current, error 0x{:X}", code)
+            }
+            GlError::DeviceContext => write!(f, "device context fetching failed"),
+            GlError::PixelFormat => write!(f, "pixel format creation failed"),
+            GlError::IncompleteFramebuffer(e) => write!(f, "framebuffer is not complete, {}", e),
+            GlError::InvalidName(name) => write!(f, "name {:?} contains a NUL byte", name),
+            GlError::UnsupportedReadFormat(format) => {
+                write!(f, "format {:?} can not be read back", format)
+            }
+            GlError::ReadbackFailed => write!(f, "pixel readback failed"),
+        }
+    }
+}
+
+impl Error for GlError {}
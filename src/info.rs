@@ -0,0 +1,176 @@
+use {
+    super::{
+        config::{Api, ContextConfig, Profile},
+        constants::{
+            ALPHA_BITS, BACK_LEFT, BLUE_BITS, CONTEXT_CORE_PROFILE_BIT, CONTEXT_PROFILE_MASK,
+            DEPTH, DEPTH_BITS, DRAW_BUFFER, FRAMEBUFFER, FRAMEBUFFER_ATTACHMENT_ALPHA_SIZE,
+            FRAMEBUFFER_ATTACHMENT_BLUE_SIZE, FRAMEBUFFER_ATTACHMENT_COLOR_ENCODING,
+            FRAMEBUFFER_ATTACHMENT_DEPTH_SIZE, FRAMEBUFFER_ATTACHMENT_GREEN_SIZE,
+            FRAMEBUFFER_ATTACHMENT_OBJECT_TYPE, FRAMEBUFFER_ATTACHMENT_RED_SIZE,
+            FRAMEBUFFER_ATTACHMENT_STENCIL_SIZE, FRONT, FRONT_LEFT, GREEN_BITS, NO_ERROR, RED_BITS,
+            RENDERER, SAMPLES, SHADING_LANGUAGE_VERSION, SRGB, STENCIL, STENCIL_BITS, VENDOR,
+            VERSION,
+        },
+        loader::Loader,
+        types::{Enumerated, SInt},
+    },
+    std::{collections::HashSet, ffi::CStr, os::raw::c_char},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextInfo {
+    pub api: Api,
+    pub version: (u8, u8),
+    pub profile: Option<Profile>,
+    pub vendor: String,
+    pub renderer: String,
+    pub version_string: String,
+    pub shading_language_version: String,
+    pub extensions: HashSet<String>,
+    pub color_bits: (u8, u8, u8, u8),
+    pub depth_bits: u8,
+    pub stencil_bits: u8,
+    pub samples: u8,
+    pub srgb: bool,
+}
+
+fn get_string(loader: &Loader, name: Enumerated) -> String {
+    let s = loader.get_string(name);
+    if s.is_null() {
+        return String::new();
+    }
+    unsafe { CStr::from_ptr(s as *const c_char) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn get_integer(loader: &Loader, name: Enumerated) -> SInt {
+    let mut v = 0;
+    loader.get_integer_v(name, &mut v);
+    v
+}
+
+// "4.6.0 NVIDIA 535.54", "4.5 (Core Profile) Mesa 23.0", "OpenGL ES 3.2 Mesa 23.0", "OpenGL ES-CM 1.1".
+pub(crate) fn parse_version(version: &str) -> (Api, (u8, u8)) {
+    let (api, rest) = match version.find("OpenGL ES") {
+        Some(i) => (Api::Gles, &version[i + 9..]),
+        None => (Api::Gl, version),
+    };
+    let rest = rest.trim_start_matches(|c: char| !c.is_ascii_digit());
+    let mut numbers = rest
+        .split(|c: char| !c.is_ascii_digit())
+        .map(|n| n.parse().unwrap_or(0));
+    let major = numbers.next().unwrap_or(0);
+    let minor = numbers.next().unwrap_or(0);
+    (api, (major, minor))
+}
+
+impl ContextInfo {
+    pub(crate) fn new(loader: &Loader, config: &ContextConfig) -> Self {
+        for _ in 0..16 {
+            if loader.get_error() == NO_ERROR {
+                break;
+            }
+        }
+        let version_string = get_string(loader, VERSION);
+        let (api, version) = parse_version(&version_string);
+        let core = api == Api::Gl
+            && version >= (3, 2)
+            && get_integer(loader, CONTEXT_PROFILE_MASK) & CONTEXT_CORE_PROFILE_BIT as SInt != 0;
+        let profile = match api {
+            Api::Gles => None,
+            Api::Gl if core => Some(Profile::Core),
+            Api::Gl => Some(Profile::Compatibility),
+        };
+
+        let mut color_bits = config.get_color_bits();
+        let mut depth_bits = config.get_depth_bits();
+        let mut stencil_bits = config.get_stencil_bits();
+        let mut srgb = config.is_srgb();
+        match (core, loader.get_framebuffer_attachment_parameteriv.is_some()) {
+            (true, true) => {
+                let color = if get_integer(loader, DRAW_BUFFER) == FRONT as SInt {
+                    FRONT_LEFT
+                } else {
+                    BACK_LEFT
+                };
+                let get = |attachment, pname| {
+                    let mut v = 0;
+                    loader.get_framebuffer_attachment_parameteriv(
+                        FRAMEBUFFER,
+                        attachment,
+                        FRAMEBUFFER_ATTACHMENT_OBJECT_TYPE,
+                        &mut v,
+                    );
+                    if v == 0 {
+                        return 0;
+                    }
+                    loader.get_framebuffer_attachment_parameteriv(
+                        FRAMEBUFFER,
+                        attachment,
+                        pname,
+                        &mut v,
+                    );
+                    v
+                };
+                let sizes = (
+                    get(color, FRAMEBUFFER_ATTACHMENT_RED_SIZE),
+                    get(color, FRAMEBUFFER_ATTACHMENT_GREEN_SIZE),
+                    get(color, FRAMEBUFFER_ATTACHMENT_BLUE_SIZE),
+                    get(color, FRAMEBUFFER_ATTACHMENT_ALPHA_SIZE),
+                    get(DEPTH, FRAMEBUFFER_ATTACHMENT_DEPTH_SIZE),
+                    get(STENCIL, FRAMEBUFFER_ATTACHMENT_STENCIL_SIZE),
+                    get(color, FRAMEBUFFER_ATTACHMENT_COLOR_ENCODING),
+                );
+                if loader.get_error() == NO_ERROR {
+                    color_bits = (sizes.0 as u8, sizes.1 as u8, sizes.2 as u8, sizes.3 as u8);
+                    depth_bits = sizes.4 as u8;
+                    stencil_bits = sizes.5 as u8;
+                    srgb = sizes.6 == SRGB as SInt;
+                }
+            }
+            (false, _) => {
+                let sizes = (
+                    get_integer(loader, RED_BITS),
+                    get_integer(loader, GREEN_BITS),
+                    get_integer(loader, BLUE_BITS),
+                    get_integer(loader, ALPHA_BITS),
+                    get_integer(loader, DEPTH_BITS),
+                    get_integer(loader, STENCIL_BITS),
+                );
+                if loader.get_error() == NO_ERROR {
+                    color_bits = (sizes.0 as u8, sizes.1 as u8, sizes.2 as u8, sizes.3 as u8);
+                    depth_bits = sizes.4 as u8;
+                    stencil_bits = sizes.5 as u8;
+                }
+            }
+            _ => (),
+        }
+        let samples = get_integer(loader, SAMPLES);
+        let samples = if loader.get_error() == NO_ERROR {
+            samples as u8
+        } else {
+            config.get_samples()
+        };
+
+        Self {
+            api,
+            version,
+            profile,
+            vendor: get_string(loader, VENDOR),
+            renderer: get_string(loader, RENDERER),
+            shading_language_version: get_string(loader, SHADING_LANGUAGE_VERSION),
+            extensions: loader.get_extensions().clone(),
+            version_string,
+            color_bits,
+            depth_bits,
+            stencil_bits,
+            samples,
+            srgb,
+        }
+    }
+
+    pub fn has_extension(&self, name: &str) -> bool {
+        self.extensions.contains(name)
+    }
+}
@@ -3,10 +3,29 @@ pub extern crate rust_graphics_window as window;
 pub use window::library_loader;
 pub use window::log;
 
+include!(concat!(env!("OUT_DIR"), "/functions.rs"));
+
+pub mod config;
 pub mod constants;
 pub(crate) mod context;
-#[cfg(target_os = "android")]
+pub mod debug;
+#[cfg(any(target_os = "android", target_os = "linux"))]
 pub(crate) mod egl;
+pub mod error;
+pub mod info;
 pub mod loader;
 pub mod manager;
+#[cfg(feature = "gl-mock")]
+pub mod mock;
+pub mod objects;
+#[cfg(target_os = "linux")]
+pub mod platform;
+pub mod program;
+pub mod readback;
+pub mod reflection;
+pub mod render_thread;
+#[cfg(feature = "gl-trace")]
+pub mod trace;
 pub mod types;
+#[cfg(target_os = "windows")]
+pub(crate) mod wgl;
@@ -1,255 +1,383 @@
+#[cfg(feature = "gl-mock")]
+use super::mock::{self, MockValue};
+#[cfg(feature = "gl-trace")]
+use super::trace::{record, ReplayArgs, TraceValue};
 use {
     super::{
+        config::Api,
+        constants::{EXTENSIONS, NUM_EXTENSIONS, VERSION},
         context::Context,
-        types::{BitField, Boolean, Enumerated, SInt, SizeI, SizeIPtr, UInt},
+        error::GlError,
+        info::parse_version,
+        types::*,
     },
     crate::window::{library_loader::Linker, log::log_i},
     std::{
+        collections::HashSet,
+        ffi::CStr,
         os::raw::{c_char, c_void},
         sync::Arc,
     },
 };
+#[cfg(all(feature = "gl-check", debug_assertions))]
+use {
+    super::{
+        constants::{ErrorCode, NO_ERROR},
+        context::get_current_on_thread,
+    },
+    crate::window::log::{log_e, log_f},
+    std::{
+        convert::TryFrom,
+        fmt::Debug,
+        sync::atomic::{AtomicBool, Ordering},
+        thread::current,
+    },
+};
 
-#[cfg_attr(debug_mode, derive(Debug))]
-pub struct Loader {
-    pub active_texture: extern "C" fn(texture: Enumerated),
-    pub attach_shader: extern "C" fn(program: UInt, shader: UInt),
-    pub bind_attrib_location: extern "C" fn(program: UInt, index: UInt, name: *const c_char),
-    pub bind_buffer: extern "C" fn(target: Enumerated, buffer: UInt),
-    pub bind_framebuffer: extern "C" fn(target: Enumerated, framebuffer: UInt),
-    pub bind_renderbuffer: extern "C" fn(target: Enumerated, renderbuffer: UInt),
-    pub bind_texture: extern "C" fn(target: Enumerated, texture: UInt),
-    pub bind_vertex_array: extern "C" fn(arr: UInt),
-    pub blend_func: extern "C" fn(s_factor: Enumerated, d_factor: Enumerated),
-    pub buffer_data: extern "C" fn(
-        target: Enumerated,
-        data_size: SizeIPtr,
-        data: *const c_void,
-        usage: Enumerated,
-    ),
-    pub check_framebuffer_status: extern "C" fn(target: Enumerated) -> Enumerated,
-    pub clear_color: extern "C" fn(red: f32, green: f32, blue: f32, alpha: f32),
-    pub clear: extern "C" fn(mask: BitField),
-    pub compile_shader: extern "C" fn(shader: UInt),
-    pub create_program: extern "C" fn() -> UInt,
-    pub create_shader: extern "C" fn(shader: Enumerated) -> UInt,
-    pub cull_face: extern "C" fn(mode: Enumerated),
-    pub delete_buffers: extern "C" fn(number: SizeI, shader: *const UInt),
-    pub delete_framebuffers: extern "C" fn(number: SizeI, framebuffers: *const UInt),
-    pub delete_program: extern "C" fn(program: UInt),
-    pub delete_renderbuffers: extern "C" fn(number: SizeI, renderbuffers: *const UInt),
-    pub delete_shader: extern "C" fn(shader: UInt),
-    pub delete_textures: extern "C" fn(number: SizeI, textures: *const UInt),
-    pub delete_vertex_arrays: extern "C" fn(number: SizeI, arrays: *const UInt),
-    pub depth_mask: extern "C" fn(flag: Boolean),
-    pub disable: extern "C" fn(cap: Enumerated),
-    pub draw_elements: extern "C" fn(
-        mode: Enumerated,
-        count: SizeI,
-        element_type: Enumerated,
-        indices: *const c_void,
-    ),
-    pub enable: extern "C" fn(cap: Enumerated),
-    pub enable_vertex_attrib_array: extern "C" fn(index: UInt),
-    pub framebuffer_renderbuffer: extern "C" fn(
-        target: Enumerated,
-        attachment: Enumerated,
-        renderbuffertarget: Enumerated,
-        renderbuffer: UInt,
-    ),
-    pub framebuffer_texture2d: extern "C" fn(
-        target: Enumerated,
-        attachment: Enumerated,
-        textarget: Enumerated,
-        texture: UInt,
-        level: SInt,
-    ),
-    pub gen_buffers: extern "C" fn(number: SizeI, buffers: *mut UInt),
-    pub gen_framebuffers: extern "C" fn(number: SizeI, framebuffers: *mut UInt),
-    pub gen_renderbuffers: extern "C" fn(number: SizeI, renderbuffers: *mut UInt),
-    pub gen_textures: extern "C" fn(number: SizeI, textures: *mut UInt),
-    pub generate_mipmap: extern "C" fn(target: Enumerated),
-    pub get_attrib_location: extern "C" fn(program: UInt, name: *const c_char) -> SInt,
-    pub get_error: extern "C" fn() -> Enumerated,
-    pub get_integer_v: extern "C" fn(pname: Enumerated, data: *mut SInt),
-    pub gen_vertex_arrays: extern "C" fn(number: SizeI, arrays: *mut UInt),
-    pub get_program_iv: extern "C" fn(program: UInt, pnamne: Enumerated, params: *mut SInt),
-    pub get_program_info_log:
-        extern "C" fn(program: UInt, buf_size: SizeI, length: *mut SizeI, info: *mut c_char),
-    pub get_shader_iv: extern "C" fn(shader: UInt, pname: Enumerated, params: *mut SInt),
-    pub get_shader_info_log:
-        extern "C" fn(shader: UInt, buf_size: SizeI, length: *mut SizeI, info: *mut c_char),
-    pub get_uniform_location: extern "C" fn(program: UInt, name: *const c_char) -> SInt,
-    pub link_program: extern "C" fn(program: UInt),
-    pub read_buffer: extern "C" fn(src: Enumerated),
-    pub renderbuffer_storage: extern "C" fn(
-        target: Enumerated,
-        internal_format: Enumerated,
-        img_width: SizeI,
-        img_height: SizeI,
-    ),
-    pub tex_image_2d: extern "C" fn(
-        target: Enumerated,
-        level: SInt,
-        internal_format: SInt,
-        image_width: SizeI,
-        image_height: SizeI,
-        border: SInt,
-        format: Enumerated,
-        data_type: Enumerated,
-        pixels: *const c_void,
-    ),
-    pub tex_parameter_f: extern "C" fn(Enumerated, Enumerated, f32),
-    pub tex_parameter_fv: extern "C" fn(Enumerated, Enumerated, *const f32),
-    pub tex_parameter_i: extern "C" fn(Enumerated, Enumerated, SInt),
-    pub tex_parameter_iv: extern "C" fn(Enumerated, Enumerated, *const SInt),
-    pub scissor: extern "C" fn(SInt, SInt, SizeI, SizeI),
-    pub shader_source:
-        extern "C" fn(shader: UInt, count: SizeI, code: *const *const c_char, length: *const SInt),
-    pub uniform_1f: extern "C" fn(location: SInt, v0: f32),
-    pub uniform_1fv: extern "C" fn(location: SInt, count: SizeI, data: *const c_void),
-    pub uniform_1i: extern "C" fn(location: SInt, v0: SInt),
-    pub uniform_1iv: extern "C" fn(location: SInt, count: SizeI, data: *const c_void),
-    pub uniform_2fv: extern "C" fn(location: SInt, count: SizeI, data: *const c_void),
-    pub uniform_3fv: extern "C" fn(location: SInt, count: SizeI, data: *const c_void),
-    pub uniform_4fv: extern "C" fn(location: SInt, count: SizeI, data: *const c_void),
-    pub uniform_matrix_2fv:
-        extern "C" fn(location: SInt, count: SizeI, transpose: Boolean, value: *const f32),
-    pub uniform_matrix_3fv:
-        extern "C" fn(location: SInt, count: SizeI, transpose: Boolean, value: *const f32),
-    pub uniform_matrix_4fv:
-        extern "C" fn(location: SInt, count: SizeI, transpose: Boolean, value: *const f32),
-    pub use_program: extern "C" fn(program: UInt),
-    pub validate_program: extern "C" fn(program: UInt),
-    pub vertex_attrib_pointer: extern "C" fn(
-        index: UInt,
-        size: SInt,
-        t: Enumerated,
-        normalized: Boolean,
-        stride: SizeI,
-        pointer: *const c_void,
-    ),
-    pub viewport: extern "C" fn(SInt, SInt, SizeI, SizeI),
-    #[cfg(any(target_os = "windows", target_os = "linux"))]
-    _library: Linker,
-    context: Arc<Context>,
-}
+macro_rules! define_loader {
+    (
+        required {
+            $($rn:ident, $rs:expr, ($($ra:ident: $rt:ty),*) $(-> $rr:ty)?;)*
+        }
+        optional {
+            $($on:ident, $os:expr, [$($oe:expr),*], [$($ov:expr),*], ($($oa:ident: $ot:ty),*) $(-> $or:ty)?;)*
+        }
+    ) => {
+        #[cfg(feature = "gl-mock")]
+        mod mocked {
+            $(pub mod $rn {
+                use super::super::*;
 
-impl Loader {
-    pub(crate) fn new(context: Arc<Context>) -> Option<Self> {
-        #[cfg(feature = "verbose-log")]
-        log_i!("Going to load OpenGL library.");
-        #[cfg(target_os = "windows")]
-        let library_name = "opengl32.dll";
-        #[cfg(target_os = "linux")]
-        let library_name = "libGL.so";
-        #[cfg(any(target_os = "windows", target_os = "linux"))]
-        let _library = if let Some(l) = Linker::new(library_name) {
-            l
-        } else {
-            log_i!("Can not load OpenGL library, {} not found.", library_name);
-            return None;
-        };
+                pub extern "C" fn call($($ra: $rt),*) $(-> $rr)? {
+                    MockValue::from_mock(mock::call($rs, &[$($ra.to_mock()),*]))
+                }
+            })*
+            $(pub mod $on {
+                use super::super::*;
 
-        #[cfg(any(target_os = "windows", target_os = "linux"))]
-        macro_rules! fun {
-            ($n:expr) => {
-                if let Some(f) = context.get_function(concat!("gl", $n)) {
-                    f
-                } else if let Some(f) = _library.get_function(concat!("gl", $n)) {
-                    f
-                } else {
-                    log_i!("Can not load function 'gl{}'", $n);
-                    return None;
+                pub extern "C" fn call($($oa: $ot),*) $(-> $or)? {
+                    MockValue::from_mock(mock::call($os, &[$($oa.to_mock()),*]))
+                }
+            })*
+        }
+
+        #[cfg_attr(debug_mode, derive(Debug))]
+        pub struct Loader {
+            $(pub $rn: extern "C" fn($($ra: $rt),*) $(-> $rr)?,)*
+            $(pub $on: Option<extern "C" fn($($oa: $ot),*) $(-> $or)?>,)*
+            missing_functions: Vec<&'static str>,
+            extensions: HashSet<String>,
+            // Only a mock loader goes without a library and a context.
+            #[cfg(all(any(target_os = "windows", target_os = "linux"), not(feature = "gl-mock")))]
+            _library: Linker,
+            #[cfg(all(any(target_os = "windows", target_os = "linux"), feature = "gl-mock"))]
+            _library: Option<Linker>,
+            #[cfg(not(feature = "gl-mock"))]
+            context: Arc<Context>,
+            #[cfg(feature = "gl-mock")]
+            context: Option<Arc<Context>>,
+        }
+
+        // The crate calls through these rather than the fields, so gl-check and gl-trace see every
+        // call made with this loader's own pointers.
+        impl Loader {
+            $(#[allow(clippy::too_many_arguments, clippy::let_unit_value)]
+            #[inline]
+            pub fn $rn(&self, $($ra: $rt),*) $(-> $rr)? {
+                #[cfg(all(feature = "gl-check", debug_assertions))]
+                self.check_thread($rs);
+                let r = (self.$rn)($($ra),*);
+                #[cfg(feature = "gl-trace")]
+                record($rs, &[$($ra.to_trace()),*], r.to_trace());
+                #[cfg(all(feature = "gl-check", debug_assertions))]
+                self.check($rs, &[$(&$ra as &dyn Debug),*]);
+                r
+            })*
+
+            $(#[allow(clippy::too_many_arguments, clippy::let_unit_value)]
+            #[inline]
+            pub fn $on(&self, $($oa: $ot),*) $(-> $or)? {
+                #[cfg(all(feature = "gl-check", debug_assertions))]
+                self.check_thread($os);
+                let r = self.$on.expect(concat!($os, " is not available"))($($oa),*);
+                #[cfg(feature = "gl-trace")]
+                record($os, &[$($oa.to_trace()),*], r.to_trace());
+                #[cfg(all(feature = "gl-check", debug_assertions))]
+                self.check($os, &[$(&$oa as &dyn Debug),*]);
+                r
+            })*
+        }
+
+        #[cfg(feature = "gl-mock")]
+        impl Loader {
+            pub fn new_mock() -> Self {
+                Self {
+                    $($rn: mocked::$rn::call,)*
+                    $($on: Some(mocked::$on::call),)*
+                    missing_functions: Vec::new(),
+                    extensions: HashSet::new(),
+                    #[cfg(any(target_os = "windows", target_os = "linux"))]
+                    _library: None,
+                    context: None,
                 }
-            };
+            }
         }
 
-        #[cfg(target_os = "android")]
-        macro_rules! fun {
-            ($n:expr) => {
-                if let Some(f) = context.get_function(concat!("gl", $n)) {
-                    f
+        #[cfg(feature = "gl-trace")]
+        impl Loader {
+            pub(crate) fn replay_call(&self, name: &str, args: &mut ReplayArgs) -> Option<u64> {
+                match name {
+                    $($rs => Some((self.$rn)($(<$rt as TraceValue>::from_trace(args)),*).to_trace()),)*
+                    $($os => self
+                        .$on
+                        .map(|f| f($(<$ot as TraceValue>::from_trace(args)),*).to_trace()),)*
+                    _ => None,
+                }
+            }
+        }
+
+        impl Loader {
+            pub(crate) fn new(context: Arc<Context>) -> Result<Self, GlError> {
+                #[cfg(feature = "verbose-log")]
+                log_i!("Going to load OpenGL library.");
+                #[cfg(target_os = "windows")]
+                let library_names: &[&'static str] = &["opengl32.dll"];
+                #[cfg(target_os = "linux")]
+                let library_names = context.get_library_names();
+                #[cfg(any(target_os = "windows", target_os = "linux"))]
+                let _library = if let Some(l) = library_names.iter().find_map(|n| Linker::new(n)) {
+                    l
                 } else {
-                    log_i!("Can not load function 'gl{}'", $n);
-                    return None;
+                    log_i!("Can not load OpenGL library, {} not found.", library_names[0]);
+                    return Err(GlError::LibraryNotFound(library_names[0]));
+                };
+
+                #[cfg(any(target_os = "windows", target_os = "linux"))]
+                macro_rules! load_one {
+                    ($n:expr) => {
+                        if let Some(f) = context.get_function($n) {
+                            Some(f)
+                        } else {
+                            _library.get_function($n)
+                        }
+                    };
+                }
+
+                #[cfg(target_os = "android")]
+                macro_rules! load_one {
+                    ($n:expr) => {
+                        context.get_function($n)
+                    };
+                }
+
+                macro_rules! fun {
+                    ($n:expr) => {
+                        if let Some(f) = load!($n) {
+                            f
+                        } else {
+                            log_i!("Can not load function '{}'", $n);
+                            return Err(GlError::FunctionNotFound($n));
+                        }
+                    };
+                }
+
+                let (api, version, extensions) = {
+                    macro_rules! load {
+                        ($n:expr) => {
+                            load_one!($n)
+                        };
+                    }
+                    let get_string = fun!("glGetString");
+                    let (api, version) = get_version(get_string);
+                    let extensions = get_extensions(
+                        get_string,
+                        fun!("glGetIntegerv"),
+                        load_one!("glGetStringi"),
+                        version,
+                    );
+                    (api, version, extensions)
+                };
+                #[cfg(feature = "verbose-log")]
+                log_i!("{} OpenGL extensions found.", extensions.len());
+                let suffixes: Vec<&str> = SUFFIXES
+                    .iter()
+                    .filter(|s| {
+                        let prefix = format!("GL_{}_", s);
+                        extensions.iter().any(|e| e.starts_with(&prefix))
+                    })
+                    .cloned()
+                    .collect();
+
+                macro_rules! load {
+                    ($n:expr) => {
+                        load_one!($n).or_else(|| {
+                            suffixes
+                                .iter()
+                                .filter(|s| !$n.ends_with(*s))
+                                .filter_map(|s| load_one!(&format!("{}{}", $n, s)))
+                                .next()
+                        })
+                    };
+                }
+
+                let mut missing_functions = Vec::new();
+
+                // Gated functions are loaded when the context version has them in core or when one of
+                // the extensions providing them is present.
+                macro_rules! opt_fun {
+                    ($n:expr, $g:expr, $v:expr) => {{
+                        let gates: &[&str] = $g;
+                        let versions: &[(Api, (u8, u8))] = $v;
+                        let f = if gates.is_empty()
+                            || gates.iter().any(|e| extensions.contains(*e))
+                            || versions.iter().any(|v| v.0 == api && v.1 <= version)
+                        {
+                            load!($n)
+                        } else {
+                            None
+                        };
+                        if f.is_none() {
+                            #[cfg(feature = "verbose-log")]
+                            log_i!("Optional function '{}' is not available", $n);
+                            missing_functions.push($n);
+                        }
+                        f
+                    }};
                 }
-            };
+
+                Ok(Self {
+                    $($rn: fun!($rs),)*
+                    $($on: opt_fun!($os, &[$($oe),*], &[$($ov),*]),)*
+                    missing_functions,
+                    extensions,
+                    #[cfg(all(any(target_os = "windows", target_os = "linux"), not(feature = "gl-mock")))]
+                    _library,
+                    #[cfg(all(any(target_os = "windows", target_os = "linux"), feature = "gl-mock"))]
+                    _library: Some(_library),
+                    #[cfg(not(feature = "gl-mock"))]
+                    context,
+                    #[cfg(feature = "gl-mock")]
+                    context: Some(context),
+                })
+            }
+        }
+    };
+}
+
+#[cfg(all(feature = "gl-check", debug_assertions))]
+static CHECK_PANICS: AtomicBool = AtomicBool::new(false);
+
+// Failed checks log an error, or panic in the calling code when this is set.
+#[cfg(feature = "gl-check")]
+pub fn set_check_panics(panics: bool) {
+    #[cfg(debug_assertions)]
+    CHECK_PANICS.store(panics, Ordering::Relaxed);
+    #[cfg(not(debug_assertions))]
+    let _ = panics;
+}
+
+#[cfg(all(feature = "gl-check", debug_assertions))]
+impl Loader {
+    fn check_thread(&self, name: &str) {
+        // The mock has no context to be current.
+        #[cfg(feature = "gl-mock")]
+        let context = match &self.context {
+            Some(context) => context.get_id(),
+            None => return,
+        };
+        #[cfg(not(feature = "gl-mock"))]
+        let context = self.context.get_id();
+        if context != 0 && context == get_current_on_thread() {
+            return;
+        }
+        if CHECK_PANICS.load(Ordering::Relaxed) {
+            log_f!(
+                "{} called on {:?} without the loader's OpenGL context current",
+                name,
+                current().id()
+            );
+        } else {
+            log_e!(
+                "{} called on {:?} without the loader's OpenGL context current",
+                name,
+                current().id()
+            );
+        }
+    }
+
+    fn check(&self, name: &str, args: &[&dyn Debug]) {
+        if name == "glGetError" {
+            return;
+        }
+        let mut errors = Vec::new();
+        for _ in 0..16 {
+            match (self.get_error)() {
+                NO_ERROR => break,
+                e => errors.push(ErrorCode::try_from(e)),
+            }
+        }
+        if errors.is_empty() {
+            return;
+        }
+        let args: Vec<String> = args.iter().map(|a| format!("{:?}", a)).collect();
+        if CHECK_PANICS.load(Ordering::Relaxed) {
+            log_f!("{}({}) failed with {:?}", name, args.join(", "), errors);
+        } else {
+            log_e!("{}({}) failed with {:?}", name, args.join(", "), errors);
         }
+    }
+}
+
+const SUFFIXES: [&str; 4] = ["ARB", "EXT", "OES", "KHR"];
+
+fn to_string(s: *const UByte) -> String {
+    if s.is_null() {
+        String::new()
+    } else {
+        unsafe { CStr::from_ptr(s as *const c_char) }
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+fn get_version(get_string: extern "C" fn(name: Enumerated) -> *const UByte) -> (Api, (u8, u8)) {
+    parse_version(&to_string(get_string(VERSION)))
+}
+
+fn get_extensions(
+    get_string: extern "C" fn(name: Enumerated) -> *const UByte,
+    get_integer_v: extern "C" fn(pname: Enumerated, data: *mut SInt),
+    get_stringi: Option<extern "C" fn(name: Enumerated, index: UInt) -> *const UByte>,
+    version: (u8, u8),
+) -> HashSet<String> {
+    match get_stringi {
+        Some(get_stringi) if version.0 >= 3 => {
+            let mut count = 0;
+            get_integer_v(NUM_EXTENSIONS, &mut count);
+            (0..count.max(0) as UInt)
+                .map(|i| to_string(get_stringi(EXTENSIONS, i)))
+                .filter(|e| !e.is_empty())
+                .collect()
+        }
+        _ => to_string(get_string(EXTENSIONS))
+            .split_whitespace()
+            .map(|e| e.to_string())
+            .collect(),
+    }
+}
+
+gl_functions!(define_loader);
+
+impl Loader {
+    pub fn missing_functions(&self) -> &[&'static str] {
+        &self.missing_functions
+    }
+
+    pub fn has_extension(&self, name: &str) -> bool {
+        self.extensions.contains(name)
+    }
 
-        Some(Self {
-            active_texture: fun!("ActiveTexture"),
-            attach_shader: fun!("AttachShader"),
-            bind_attrib_location: fun!("BindAttribLocation"),
-            bind_buffer: fun!("BindBuffer"),
-            bind_framebuffer: fun!("BindFramebuffer"),
-            bind_renderbuffer: fun!("BindRenderbuffer"),
-            bind_texture: fun!("BindTexture"),
-            bind_vertex_array: fun!("BindVertexArray"),
-            blend_func: fun!("BlendFunc"),
-            buffer_data: fun!("BufferData"),
-            check_framebuffer_status: fun!("CheckFramebufferStatus"),
-            clear_color: fun!("ClearColor"),
-            clear: fun!("Clear"),
-            compile_shader: fun!("CompileShader"),
-            create_program: fun!("CreateProgram"),
-            create_shader: fun!("CreateShader"),
-            cull_face: fun!("CullFace"),
-            delete_buffers: fun!("DeleteBuffers"),
-            delete_framebuffers: fun!("DeleteFramebuffers"),
-            delete_program: fun!("DeleteProgram"),
-            delete_renderbuffers: fun!("DeleteRenderbuffers"),
-            delete_shader: fun!("DeleteShader"),
-            delete_textures: fun!("DeleteTextures"),
-            delete_vertex_arrays: fun!("DeleteVertexArrays"),
-            depth_mask: fun!("DepthMask"),
-            disable: fun!("Disable"),
-            draw_elements: fun!("DrawElements"),
-            enable: fun!("Enable"),
-            enable_vertex_attrib_array: fun!("EnableVertexAttribArray"),
-            framebuffer_renderbuffer: fun!("FramebufferRenderbuffer"),
-            framebuffer_texture2d: fun!("FramebufferTexture2D"),
-            gen_buffers: fun!("GenBuffers"),
-            gen_framebuffers: fun!("GenFramebuffers"),
-            gen_renderbuffers: fun!("GenRenderbuffers"),
-            gen_textures: fun!("GenTextures"),
-            generate_mipmap: fun!("GenerateMipmap"),
-            get_attrib_location: fun!("GetAttribLocation"),
-            get_error: fun!("GetError"),
-            get_integer_v: fun!("GetIntegerv"),
-            gen_vertex_arrays: fun!("GenVertexArrays"),
-            get_program_iv: fun!("GetProgramiv"),
-            get_program_info_log: fun!("GetProgramInfoLog"),
-            get_shader_iv: fun!("GetShaderiv"),
-            get_shader_info_log: fun!("GetShaderInfoLog"),
-            get_uniform_location: fun!("GetUniformLocation"),
-            link_program: fun!("LinkProgram"),
-            read_buffer: fun!("ReadBuffer"),
-            renderbuffer_storage: fun!("RenderbufferStorage"),
-            tex_image_2d: fun!("TexImage2D"),
-            tex_parameter_f: fun!("TexParameterf"),
-            tex_parameter_fv: fun!("TexParameterfv"),
-            tex_parameter_i: fun!("TexParameteri"),
-            tex_parameter_iv: fun!("TexParameteriv"),
-            scissor: fun!("Scissor"),
-            shader_source: fun!("ShaderSource"),
-            uniform_1f: fun!("Uniform1f"),
-            uniform_1fv: fun!("Uniform1fv"),
-            uniform_1i: fun!("Uniform1i"),
-            uniform_1iv: fun!("Uniform1iv"),
-            uniform_2fv: fun!("Uniform2fv"),
-            uniform_3fv: fun!("Uniform3fv"),
-            uniform_4fv: fun!("Uniform4fv"),
-            uniform_matrix_2fv: fun!("UniformMatrix2fv"),
-            uniform_matrix_3fv: fun!("UniformMatrix3fv"),
-            uniform_matrix_4fv: fun!("UniformMatrix4fv"),
-            use_program: fun!("UseProgram"),
-            validate_program: fun!("ValidateProgram"),
-            vertex_attrib_pointer: fun!("VertexAttribPointer"),
-            viewport: fun!("Viewport"),
-            #[cfg(any(target_os = "windows", target_os = "linux"))]
-            _library,
-            context,
-        })
+    pub fn get_extensions(&self) -> &HashSet<String> {
+        &self.extensions
     }
 }
@@ -1,53 +1,369 @@
+#[cfg(target_os = "linux")]
+use super::platform::Platform;
 #[cfg(feature = "verbose-log")]
 use crate::window::log::log_i;
 use {
-    super::{context::Context, loader::Loader},
-    crate::window::{event::Listener, Window},
-    std::sync::Arc,
+    super::{
+        config::ContextConfig,
+        constants::InternalFormat,
+        context::Context,
+        error::{GlError, SwapStatus},
+        info::ContextInfo,
+        loader::Loader,
+        readback::{read_pixels, PixelReadback, Rect},
+    },
+    crate::window::{
+        event::{Event, Listener, Window as WindowEvent},
+        log::log_e,
+        Window,
+    },
+    std::{marker::PhantomData, sync::Arc},
 };
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderEvent {
+    Resized { width: u32, height: u32 },
+    SurfaceCreated,
+    SurfaceDestroyed,
+    ContextLost,
+    Paused,
+    Resumed,
+    Focus(bool),
+}
+
+pub type RenderCallback = Box<dyn FnMut(&RenderEvent)>;
+
 pub struct Manager {
-    window: Arc<Window>,
+    window: Option<Arc<Window>>,
     context: Arc<Context>,
     loader: Arc<Loader>,
+    info: ContextInfo,
+    size: (u32, u32),
+    paused: bool,
+    callbacks: Vec<RenderCallback>,
+}
+
+pub struct CurrentGuard<'a> {
+    context: &'a Context,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl<'a> Drop for CurrentGuard<'a> {
+    fn drop(&mut self) {
+        self.context.release_current();
+    }
 }
 
 impl Manager {
-    pub fn new(window: Arc<Window>) -> Option<Self> {
+    pub fn new(window: Arc<Window>, config: ContextConfig) -> Result<Self, GlError> {
         #[cfg(feature = "verbose-log")]
         log_i!("Start of OpenGL manager.");
-        let context = if let Some(context) = Context::new(window.clone()) {
-            Arc::new(context)
-        } else {
-            return None;
-        };
+        let context = Context::new(window.clone(), &config)?;
+        Self::with_context(Some(window), context)
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn new_headless(width: u32, height: u32, config: ContextConfig) -> Result<Self, GlError> {
+        #[cfg(feature = "verbose-log")]
+        log_i!("Start of headless OpenGL manager.");
+        let context = Context::new_headless(width, height, &config)?;
+        let mut manager = Self::with_context(None, context)?;
+        manager.size = (width, height);
+        Ok(manager)
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn new_egl(
+        window: Option<Arc<Window>>,
+        platform: Platform,
+        config: ContextConfig,
+    ) -> Result<Self, GlError> {
+        #[cfg(feature = "verbose-log")]
+        log_i!("Start of OpenGL manager on EGL {:?} platform.", platform);
+        let context = Context::new_egl(window.clone(), platform, &config, 0, 0)?;
+        Self::with_context(window, context)
+    }
+
+    #[cfg(all(
+        test,
+        feature = "gl-mock",
+        any(target_os = "android", target_os = "linux")
+    ))]
+    #[allow(clippy::arc_with_non_send_sync)]
+    pub(crate) fn new_mock() -> Self {
+        let context = Arc::new(Context::new_mock());
+        let loader = Arc::new(Loader::new_mock());
+        let info = ContextInfo::new(&loader, context.get_config());
+        Self {
+            window: None,
+            context,
+            loader,
+            info,
+            size: (0, 0),
+            paused: false,
+            callbacks: Vec::new(),
+        }
+    }
+
+    fn with_context(window: Option<Arc<Window>>, context: Context) -> Result<Self, GlError> {
         #[cfg(feature = "verbose-log")]
         log_i!("OpenGL context created.");
-        let loader = if let Some(loader) = Loader::new(context.clone()) {
-            Arc::new(loader)
-        } else {
-            return None;
-        };
+        let context = Arc::new(context);
+        let loader = Arc::new(Loader::new(context.clone())?);
         #[cfg(feature = "verbose-log")]
         log_i!("OpenGL library loaded.");
-        Some(Self {
+        let info = ContextInfo::new(&loader, context.get_config());
+        #[cfg(feature = "verbose-log")]
+        log_i!(
+            "OpenGL {:?} {}.{} on {}",
+            info.api,
+            info.version.0,
+            info.version.1,
+            info.renderer
+        );
+        let size = context.get_surface_size().unwrap_or((0, 0));
+        Ok(Self {
             window,
             context,
             loader,
+            info,
+            size,
+            paused: false,
+            callbacks: Vec::new(),
+        })
+    }
+
+    pub fn get_loader(&self) -> &Arc<Loader> {
+        &self.loader
+    }
+
+    pub fn get_config(&self) -> &ContextConfig {
+        self.context.get_config()
+    }
+
+    pub fn get_context_info(&self) -> &ContextInfo {
+        &self.info
+    }
+
+    pub fn get_size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn add_callback<F: FnMut(&RenderEvent) + 'static>(&mut self, callback: F) {
+        self.callbacks.push(Box::new(callback));
+    }
+
+    fn notify(&mut self, event: RenderEvent) {
+        for callback in &mut self.callbacks {
+            callback(&event);
+        }
+    }
+
+    // A recreated context has no viewport yet, so it is forced to be set even at the same size.
+    fn resize(&mut self, width: u32, height: u32, force: bool) {
+        let size = self.context.get_surface_size().unwrap_or((width, height));
+        if size == self.size && !force {
+            return;
+        }
+        #[cfg(feature = "verbose-log")]
+        log_i!("OpenGL drawable resized to {}x{}.", size.0, size.1);
+        self.size = size;
+        self.loader.viewport(0, 0, size.0, size.1);
+        self.notify(RenderEvent::Resized {
+            width: size.0,
+            height: size.1,
+        });
+    }
+
+    pub fn swap_buffers(&self) -> SwapStatus {
+        self.context.swap()
+    }
+
+    // 0 turns vsync off, -1 asks for adaptive vsync where it is supported.
+    pub fn set_swap_interval(&self, interval: i32) -> Option<i32> {
+        let accepted = self.context.set_swap_interval(interval);
+        #[cfg(feature = "verbose-log")]
+        log_i!(
+            "OpenGL swap interval {} requested, {:?} accepted.",
+            interval,
+            accepted
+        );
+        accepted
+    }
+
+    // The interval in effect on the current surface, None while there is no surface.
+    pub fn get_swap_interval(&self) -> Option<i32> {
+        self.context.get_swap_interval()
+    }
+
+    // The manager can not leave the thread that created it, so these only switch between its
+    // context and others on that thread. Rendering on another thread goes through a context from
+    // create_shared_context.
+    pub fn make_current(&self) -> Result<(), GlError> {
+        self.context.make_current()
+    }
+
+    pub fn make_current_guard<'a>(&'a self) -> Result<CurrentGuard<'a>, GlError> {
+        self.context.make_current()?;
+        Ok(CurrentGuard {
+            context: &self.context,
+            _not_send: PhantomData,
         })
     }
 
-    pub fn get_loader(&self) -> &Loader {
-        self.loader.as_ref()
+    pub fn release_current(&self) {
+        self.context.release_current();
+    }
+
+    // Reads from the bound read framebuffer, rows start at the bottom of the rectangle.
+    pub fn read_pixels(&self, rect: Rect, format: InternalFormat) -> Result<Vec<u8>, GlError> {
+        read_pixels(&self.loader, rect, format)
+    }
+
+    // Falls back to a synchronous read when pixel pack buffers or fences are missing.
+    pub fn read_pixels_async(
+        &self,
+        rect: Rect,
+        format: InternalFormat,
+    ) -> Result<PixelReadback, GlError> {
+        PixelReadback::new(&self.loader, rect, format)
+    }
+
+    // Worker contexts borrow the window's display or device, drop them before the manager.
+    pub fn create_shared_context(&self) -> Result<SharedContext, GlError> {
+        let mut context = self.context.create_shared()?;
+        // Creating it made it current, the caller gets its own context back.
+        context.release_current();
+        context.release_device();
+        self.context.make_current()?;
+        #[cfg(feature = "verbose-log")]
+        log_i!("Shared OpenGL context created.");
+        Ok(SharedContext { context })
+    }
+
+    pub fn suspend(&self) {
+        #[cfg(feature = "verbose-log")]
+        log_i!("OpenGL surface suspended.");
+        self.context.suspend();
     }
 
-    pub fn swap_buffers(&self) {
-        self.context.swap();
+    // A lost context is recreated, the loader and the context info are reloaded for it, so
+    // loaders fetched before it must be fetched again.
+    #[allow(clippy::arc_with_non_send_sync)]
+    pub fn resume(&mut self) -> Result<SwapStatus, GlError> {
+        #[cfg(feature = "verbose-log")]
+        log_i!("OpenGL surface resumed.");
+        let status = match &self.window {
+            Some(window) => self.context.resume(window)?,
+            None => SwapStatus::Ok,
+        };
+        if status == SwapStatus::ContextLost {
+            self.loader = Arc::new(Loader::new(self.context.clone())?);
+            self.info = ContextInfo::new(&self.loader, self.context.get_config());
+            #[cfg(feature = "verbose-log")]
+            log_i!("OpenGL library reloaded for the recreated context.");
+        }
+        Ok(status)
     }
 }
 
-// impl Listener for Manager {
-//     fn on_event(&mut self, event: &Event) -> bool {
-//         false
-//     }
-// }
+pub struct SharedContext {
+    context: Context,
+}
+
+// Nothing else refers to the context, it keeps no window and it is not current on any thread.
+// It holds no device context on Windows and has its own display connection on GLX, so nothing in
+// it belongs to the creating thread.
+unsafe impl Send for SharedContext {}
+
+impl SharedContext {
+    pub fn get_config(&self) -> &ContextConfig {
+        self.context.get_config()
+    }
+
+    // Makes the context current on the calling thread and loads its functions there.
+    #[allow(clippy::arc_with_non_send_sync)]
+    pub fn into_worker(mut self) -> Result<WorkerContext, GlError> {
+        self.context.acquire_device()?;
+        self.context.make_current()?;
+        let context = Arc::new(self.context);
+        let loader = Arc::new(Loader::new(context.clone())?);
+        Ok(WorkerContext { context, loader })
+    }
+}
+
+pub struct WorkerContext {
+    context: Arc<Context>,
+    loader: Arc<Loader>,
+}
+
+impl WorkerContext {
+    pub fn make_current(&self) -> Result<(), GlError> {
+        self.context.make_current()
+    }
+
+    pub fn make_current_guard<'a>(&'a self) -> Result<CurrentGuard<'a>, GlError> {
+        self.context.make_current()?;
+        Ok(CurrentGuard {
+            context: &self.context,
+            _not_send: PhantomData,
+        })
+    }
+
+    pub fn release_current(&self) {
+        self.context.release_current();
+    }
+
+    pub fn get_loader(&self) -> &Arc<Loader> {
+        &self.loader
+    }
+
+    pub fn get_config(&self) -> &ContextConfig {
+        self.context.get_config()
+    }
+}
+
+impl Listener for Manager {
+    fn on_event(&mut self, event: &Event) -> bool {
+        let event = if let Event::Window(e) = event {
+            e
+        } else {
+            return false;
+        };
+        match *event {
+            WindowEvent::SizeChange { width, height } => self.resize(width, height, false),
+            WindowEvent::SurfaceDestroyed => {
+                self.suspend();
+                self.notify(RenderEvent::SurfaceDestroyed);
+            }
+            WindowEvent::SurfaceCreated => match self.resume() {
+                Ok(status) => {
+                    let lost = status == SwapStatus::ContextLost;
+                    if lost {
+                        self.notify(RenderEvent::ContextLost);
+                    }
+                    let size = self.size;
+                    self.resize(size.0, size.1, lost);
+                    self.notify(RenderEvent::SurfaceCreated);
+                }
+                Err(e) => log_e!("Can not resume the OpenGL surface: {}", e),
+            },
+            WindowEvent::Pause => {
+                self.paused = true;
+                self.notify(RenderEvent::Paused);
+            }
+            WindowEvent::Resume => {
+                self.paused = false;
+                self.notify(RenderEvent::Resumed);
+            }
+            WindowEvent::Focus => self.notify(RenderEvent::Focus(true)),
+            WindowEvent::Unfocus => self.notify(RenderEvent::Focus(false)),
+            _ => (),
+        }
+        false
+    }
+}
@@ -0,0 +1,576 @@
+use {
+    super::{
+        constants::{self, INVALID_ENUM, INVALID_OPERATION, INVALID_VALUE},
+        types::{DebugProc, Enumerated, SInt, UInt},
+    },
+    std::{
+        cell::RefCell,
+        collections::HashMap,
+        ffi::CStr,
+        os::raw::c_char,
+        ptr::{copy_nonoverlapping, null, write_bytes},
+        slice::from_raw_parts,
+    },
+};
+
+// Call arguments are widened to u64, so the values matched against them are too.
+const TEXTURE0: u64 = constants::TEXTURE0 as u64;
+const VERSION: u64 = constants::VERSION as u64;
+const VENDOR: u64 = constants::VENDOR as u64;
+const RENDERER: u64 = constants::RENDERER as u64;
+const SHADING_LANGUAGE_VERSION: u64 = constants::SHADING_LANGUAGE_VERSION as u64;
+const EXTENSIONS: u64 = constants::EXTENSIONS as u64;
+const COMPILE_STATUS: u64 = constants::COMPILE_STATUS as u64;
+const LINK_STATUS: u64 = constants::LINK_STATUS as u64;
+const VALIDATE_STATUS: u64 = constants::VALIDATE_STATUS as u64;
+const SHADER_TYPE: u64 = constants::SHADER_TYPE as u64;
+const SHADER_SOURCE_LENGTH: u64 = constants::SHADER_SOURCE_LENGTH as u64;
+const ATTACHED_SHADERS: u64 = constants::ATTACHED_SHADERS as u64;
+const BUFFER_SIZE: u64 = constants::BUFFER_SIZE as u64;
+const FRAMEBUFFER_COMPLETE: u64 = constants::FRAMEBUFFER_COMPLETE as u64;
+const PIXEL_PACK_BUFFER: u64 = constants::PIXEL_PACK_BUFFER as u64;
+const PIXEL_PACK_BUFFER_BINDING: u64 = constants::PIXEL_PACK_BUFFER_BINDING as u64;
+const ALREADY_SIGNALED: u64 = constants::ALREADY_SIGNALED as u64;
+
+pub(crate) trait MockValue {
+    fn to_mock(&self) -> u64;
+    fn from_mock(v: u64) -> Self;
+}
+
+macro_rules! mock_integer {
+    ($($t:ty),*) => {
+        $(impl MockValue for $t {
+            fn to_mock(&self) -> u64 {
+                *self as u64
+            }
+
+            fn from_mock(v: u64) -> Self {
+                v as $t
+            }
+        })*
+    };
+}
+
+mock_integer!(u8, i8, u16, i16, u32, i32, u64, i64, isize, usize);
+
+impl MockValue for f32 {
+    fn to_mock(&self) -> u64 {
+        self.to_bits() as u64
+    }
+
+    fn from_mock(v: u64) -> Self {
+        f32::from_bits(v as u32)
+    }
+}
+
+impl MockValue for f64 {
+    fn to_mock(&self) -> u64 {
+        self.to_bits()
+    }
+
+    fn from_mock(v: u64) -> Self {
+        f64::from_bits(v)
+    }
+}
+
+impl<T> MockValue for *const T {
+    fn to_mock(&self) -> u64 {
+        *self as usize as u64
+    }
+
+    fn from_mock(v: u64) -> Self {
+        v as usize as *const T
+    }
+}
+
+impl<T> MockValue for *mut T {
+    fn to_mock(&self) -> u64 {
+        *self as usize as u64
+    }
+
+    fn from_mock(v: u64) -> Self {
+        v as usize as *mut T
+    }
+}
+
+impl MockValue for DebugProc {
+    fn to_mock(&self) -> u64 {
+        self.map_or(0, |f| f as usize as u64)
+    }
+
+    fn from_mock(_: u64) -> Self {
+        None
+    }
+}
+
+impl MockValue for () {
+    fn to_mock(&self) -> u64 {
+        0
+    }
+
+    fn from_mock(_: u64) -> Self {}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockCall {
+    pub name: &'static str,
+    pub args: Vec<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockShader {
+    pub shader_type: Enumerated,
+    pub source: String,
+}
+
+#[derive(Default)]
+struct State {
+    calls: Vec<MockCall>,
+    next_name: UInt,
+    buffers: HashMap<UInt, Vec<u8>>,
+    buffer_bindings: HashMap<Enumerated, UInt>,
+    active_texture: UInt,
+    texture_bindings: HashMap<(UInt, Enumerated), UInt>,
+    shaders: HashMap<UInt, MockShader>,
+    programs: HashMap<UInt, Vec<UInt>>,
+    uniform_locations: HashMap<(UInt, String), SInt>,
+    current_program: UInt,
+    vertex_array: UInt,
+    framebuffer: UInt,
+    framebuffer_attachments: HashMap<(UInt, Enumerated), UInt>,
+    incomplete_attachment: Option<(Enumerated, Enumerated)>,
+    renderbuffer: UInt,
+    integers: HashMap<Enumerated, SInt>,
+    version: Option<&'static [u8]>,
+    pixels: Vec<u8>,
+    error: Enumerated,
+}
+
+// Every test runs on its own thread, so a thread local state keeps them apart.
+thread_local! {
+    static STATE: RefCell<State> = RefCell::new(State::default());
+}
+
+fn with_state<T>(f: impl FnOnce(&mut State) -> T) -> T {
+    STATE.with(|s| f(&mut s.borrow_mut()))
+}
+
+unsafe fn write_values<T: Copy>(pointer: u64, values: &[T]) {
+    if pointer != 0 {
+        copy_nonoverlapping(values.as_ptr(), pointer as usize as *mut T, values.len());
+    }
+}
+
+unsafe fn read_string(pointer: u64, length: Option<usize>) -> String {
+    if pointer == 0 {
+        return String::new();
+    }
+    let bytes = match length {
+        Some(l) => from_raw_parts(pointer as usize as *const u8, l),
+        None => CStr::from_ptr(pointer as usize as *const c_char).to_bytes(),
+    };
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+fn get_string(name: u64) -> *const c_char {
+    let s: &'static [u8] = match name {
+        VERSION => b"4.6 Mock\0",
+        VENDOR => b"rust-graphics\0",
+        RENDERER => b"Mock\0",
+        SHADING_LANGUAGE_VERSION => b"4.60\0",
+        EXTENSIONS => b"\0",
+        _ => return null(),
+    };
+    s.as_ptr() as *const c_char
+}
+
+impl State {
+    fn generate(&mut self, n: u64, pointer: u64) -> Vec<UInt> {
+        let names: Vec<UInt> = (0..n as UInt).map(|i| self.next_name + i + 1).collect();
+        self.next_name += n as UInt;
+        unsafe { write_values(pointer, &names) };
+        names
+    }
+
+    fn get_bound_buffer(&mut self, target: u64) -> Option<&mut Vec<u8>> {
+        let name = *self.buffer_bindings.get(&(target as Enumerated))?;
+        self.buffers.get_mut(&name)
+    }
+
+    fn call(&mut self, name: &'static str, args: &[u64]) -> u64 {
+        self.calls.push(MockCall {
+            name,
+            args: args.to_vec(),
+        });
+        let deleted = || unsafe {
+            if args[1] == 0 {
+                Vec::new()
+            } else {
+                from_raw_parts(args[1] as usize as *const UInt, args[0] as usize).to_vec()
+            }
+        };
+        match name {
+            "glGetError" => return std::mem::replace(&mut self.error, 0) as u64,
+            "glGetString" => match self.version {
+                Some(version) if args[0] == VERSION => return version.as_ptr() as usize as u64,
+                _ => return get_string(args[0]) as usize as u64,
+            },
+            "glGetIntegerv" => {
+                let value = match args[0] {
+                    PIXEL_PACK_BUFFER_BINDING => self
+                        .buffer_bindings
+                        .get(&(PIXEL_PACK_BUFFER as Enumerated))
+                        .map(|&b| b as SInt),
+                    name => self.integers.get(&(name as Enumerated)).cloned(),
+                };
+                unsafe { write_values(args[1], &[value.unwrap_or(0)]) };
+            }
+            "glPixelStorei" => {
+                self.integers.insert(args[0] as Enumerated, args[1] as SInt);
+            }
+            "glGetFloatv" | "glGetBooleanv" => unsafe {
+                if args[1] != 0 {
+                    let size = if name == "glGetBooleanv" { 1 } else { 4 };
+                    write_bytes(args[1] as usize as *mut u8, 0, size);
+                }
+            },
+            "glGenBuffers" => {
+                for b in self.generate(args[0], args[1]) {
+                    self.buffers.insert(b, Vec::new());
+                }
+            }
+            "glGenTextures"
+            | "glGenFramebuffers"
+            | "glGenRenderbuffers"
+            | "glGenVertexArrays"
+            | "glGenQueries"
+            | "glGenSamplers"
+            | "glGenTransformFeedbacks" => {
+                self.generate(args[0], args[1]);
+            }
+            "glDeleteBuffers" => {
+                for b in deleted() {
+                    self.buffers.remove(&b);
+                    self.buffer_bindings.retain(|_, v| *v != b);
+                }
+            }
+            "glDeleteTextures" => {
+                for t in deleted() {
+                    self.texture_bindings.retain(|_, v| *v != t);
+                }
+            }
+            "glDeleteFramebuffers" => {
+                for f in deleted() {
+                    if self.framebuffer == f {
+                        self.framebuffer = 0;
+                    }
+                }
+            }
+            "glDeleteRenderbuffers" => {
+                for r in deleted() {
+                    if self.renderbuffer == r {
+                        self.renderbuffer = 0;
+                    }
+                }
+            }
+            "glDeleteVertexArrays" => {
+                for a in deleted() {
+                    if self.vertex_array == a {
+                        self.vertex_array = 0;
+                    }
+                }
+            }
+            "glBindBuffer" => {
+                self.buffer_bindings
+                    .insert(args[0] as Enumerated, args[1] as UInt);
+                if args[1] != 0 {
+                    self.buffers.entry(args[1] as UInt).or_default();
+                }
+            }
+            "glBindBufferBase" | "glBindBufferRange" => {
+                self.buffer_bindings
+                    .insert(args[0] as Enumerated, args[2] as UInt);
+            }
+            "glBufferData" => {
+                if let Some(buffer) = self.get_bound_buffer(args[0]) {
+                    *buffer = if args[2] == 0 {
+                        vec![0; args[1] as usize]
+                    } else {
+                        unsafe { from_raw_parts(args[2] as usize as *const u8, args[1] as usize) }
+                            .to_vec()
+                    };
+                }
+            }
+            "glBufferSubData" => {
+                let (offset, size) = (args[1] as i64, args[2] as i64);
+                let error = match self.get_bound_buffer(args[0]) {
+                    Some(buffer)
+                        if offset >= 0
+                            && size >= 0
+                            && matches!(offset.checked_add(size), Some(end) if end as usize <= buffer.len()) =>
+                    {
+                        let (offset, size) = (offset as usize, size as usize);
+                        let data = unsafe { from_raw_parts(args[3] as usize as *const u8, size) };
+                        buffer[offset..offset + size].copy_from_slice(data);
+                        return 0;
+                    }
+                    Some(_) => INVALID_VALUE,
+                    None => INVALID_OPERATION,
+                };
+                self.error = error;
+            }
+            "glGetBufferParameteriv" if args[1] == BUFFER_SIZE => {
+                let size = self.get_bound_buffer(args[0]).map_or(0, |b| b.len()) as SInt;
+                unsafe { write_values(args[2], &[size]) };
+            }
+            // This runs inside an extern "C" fn, a bad range has to end up in glGetError rather than panic.
+            "glMapBufferRange" => {
+                let (offset, length) = (args[1] as i64, args[2] as i64);
+                let error = match self.get_bound_buffer(args[0]) {
+                    Some(buffer)
+                        if offset >= 0
+                            && length >= 0
+                            && matches!(offset.checked_add(length), Some(end) if end as usize <= buffer.len()) =>
+                    {
+                        return buffer[offset as usize..].as_mut_ptr() as usize as u64;
+                    }
+                    Some(_) => INVALID_VALUE,
+                    None => INVALID_OPERATION,
+                };
+                self.error = error;
+            }
+            "glUnmapBuffer" => return 1,
+            // Into the bound pixel pack buffer at the offset, or else to the pointer.
+            "glReadPixels" => {
+                let (offset, size) = (args[6] as usize, self.pixels.len());
+                let pixels = std::mem::take(&mut self.pixels);
+                let fits = match self.get_bound_buffer(PIXEL_PACK_BUFFER) {
+                    Some(buffer) if matches!(offset.checked_add(size), Some(end) if end <= buffer.len()) =>
+                    {
+                        buffer[offset..offset + size].copy_from_slice(&pixels);
+                        true
+                    }
+                    Some(_) => false,
+                    None => {
+                        unsafe { write_values(args[6], &pixels) };
+                        true
+                    }
+                };
+                if !fits {
+                    self.error = INVALID_OPERATION;
+                }
+                self.pixels = pixels;
+            }
+            "glFenceSync" => return 1,
+            "glClientWaitSync" => return ALREADY_SIGNALED,
+            "glActiveTexture" => match args[0].checked_sub(TEXTURE0) {
+                Some(unit) => self.active_texture = unit as UInt,
+                None => self.error = INVALID_ENUM,
+            },
+            "glBindTexture" => {
+                self.texture_bindings.insert(
+                    (self.active_texture, args[0] as Enumerated),
+                    args[1] as UInt,
+                );
+            }
+            "glBindFramebuffer" => self.framebuffer = args[1] as UInt,
+            "glBindRenderbuffer" => self.renderbuffer = args[1] as UInt,
+            "glBindVertexArray" => self.vertex_array = args[0] as UInt,
+            "glFramebufferTexture2D" | "glFramebufferRenderbuffer" => {
+                self.framebuffer_attachments
+                    .insert((self.framebuffer, args[1] as Enumerated), args[3] as UInt);
+            }
+            "glCheckFramebufferStatus" => {
+                return match self.incomplete_attachment {
+                    Some((attachment, status))
+                        if self
+                            .framebuffer_attachments
+                            .get(&(self.framebuffer, attachment))
+                            .cloned()
+                            .unwrap_or(0)
+                            != 0 =>
+                    {
+                        status as u64
+                    }
+                    _ => FRAMEBUFFER_COMPLETE,
+                };
+            }
+            "glCreateShader" => {
+                let name = self.generate(1, 0)[0];
+                self.shaders.insert(
+                    name,
+                    MockShader {
+                        shader_type: args[0] as Enumerated,
+                        source: String::new(),
+                    },
+                );
+                return name as u64;
+            }
+            "glShaderSource" => {
+                let mut source = String::new();
+                for i in 0..args[1] as usize {
+                    unsafe {
+                        let s = *(args[2] as usize as *const usize).add(i) as u64;
+                        let length = if args[3] == 0 {
+                            None
+                        } else {
+                            let l = *(args[3] as usize as *const SInt).add(i);
+                            if l < 0 {
+                                None
+                            } else {
+                                Some(l as usize)
+                            }
+                        };
+                        source.push_str(&read_string(s, length));
+                    }
+                }
+                if let Some(shader) = self.shaders.get_mut(&(args[0] as UInt)) {
+                    shader.source = source;
+                }
+            }
+            "glDeleteShader" => {
+                self.shaders.remove(&(args[0] as UInt));
+            }
+            "glCreateProgram" => {
+                let name = self.generate(1, 0)[0];
+                self.programs.insert(name, Vec::new());
+                return name as u64;
+            }
+            "glAttachShader" => {
+                if let Some(p) = self.programs.get_mut(&(args[0] as UInt)) {
+                    p.push(args[1] as UInt);
+                }
+            }
+            "glDetachShader" => {
+                if let Some(p) = self.programs.get_mut(&(args[0] as UInt)) {
+                    p.retain(|s| *s != args[1] as UInt);
+                }
+            }
+            "glDeleteProgram" => {
+                self.programs.remove(&(args[0] as UInt));
+            }
+            "glUseProgram" => self.current_program = args[0] as UInt,
+            "glGetShaderiv" => {
+                let shader = self.shaders.get(&(args[0] as UInt));
+                let v = match args[1] {
+                    COMPILE_STATUS => 1,
+                    SHADER_TYPE => shader.map_or(0, |s| s.shader_type as SInt),
+                    SHADER_SOURCE_LENGTH => shader.map_or(0, |s| s.source.len() as SInt + 1),
+                    _ => 0,
+                };
+                unsafe { write_values(args[2], &[v]) };
+            }
+            "glGetProgramiv" => {
+                let v = match args[1] {
+                    LINK_STATUS | VALIDATE_STATUS => 1,
+                    ATTACHED_SHADERS => self
+                        .programs
+                        .get(&(args[0] as UInt))
+                        .map_or(0, |p| p.len() as SInt),
+                    _ => 0,
+                };
+                unsafe { write_values(args[2], &[v]) };
+            }
+            "glGetUniformLocation" | "glGetAttribLocation" => {
+                let key = (args[0] as UInt, unsafe { read_string(args[1], None) });
+                let next = self.uniform_locations.len() as SInt;
+                return *self.uniform_locations.entry(key).or_insert(next) as u64;
+            }
+            _ => (),
+        }
+        0
+    }
+}
+
+pub(crate) fn call(name: &'static str, args: &[u64]) -> u64 {
+    with_state(|s| s.call(name, args))
+}
+
+pub fn reset() {
+    with_state(|s| *s = State::default());
+}
+
+// A loader on a fresh mock state, shared by the test modules.
+#[cfg(test)]
+#[allow(clippy::arc_with_non_send_sync)]
+pub(crate) fn new_loader() -> std::sync::Arc<super::loader::Loader> {
+    reset();
+    std::sync::Arc::new(super::loader::Loader::new_mock())
+}
+
+pub fn get_calls() -> Vec<MockCall> {
+    with_state(|s| s.calls.clone())
+}
+
+pub fn get_calls_of(name: &str) -> Vec<MockCall> {
+    with_state(|s| s.calls.iter().filter(|c| c.name == name).cloned().collect())
+}
+
+pub fn clear_calls() {
+    with_state(|s| s.calls.clear());
+}
+
+pub fn set_error(error: Enumerated) {
+    with_state(|s| s.error = error);
+}
+
+// The bound framebuffer reports status while an image is attached at attachment.
+pub fn set_incomplete_attachment(attachment: Enumerated, status: Enumerated) {
+    with_state(|s| s.incomplete_attachment = Some((attachment, status)));
+}
+
+// Answers glGetIntegerv for name, glPixelStorei sets these too.
+pub fn set_integer(name: Enumerated, value: SInt) {
+    with_state(|s| s.integers.insert(name, value));
+}
+
+// The version string must end with a nul.
+pub fn set_version(version: &'static [u8]) {
+    with_state(|s| s.version = Some(version));
+}
+
+// What glReadPixels writes, whatever the rect and format.
+pub fn set_pixels(pixels: &[u8]) {
+    with_state(|s| s.pixels = pixels.to_vec());
+}
+
+pub fn get_buffer_data(buffer: UInt) -> Option<Vec<u8>> {
+    with_state(|s| s.buffers.get(&buffer).cloned())
+}
+
+pub fn get_bound_buffer(target: Enumerated) -> UInt {
+    with_state(|s| s.buffer_bindings.get(&target).cloned().unwrap_or(0))
+}
+
+pub fn get_bound_texture(unit: UInt, target: Enumerated) -> UInt {
+    with_state(|s| {
+        s.texture_bindings
+            .get(&(unit, target))
+            .cloned()
+            .unwrap_or(0)
+    })
+}
+
+pub fn get_shader(shader: UInt) -> Option<MockShader> {
+    with_state(|s| s.shaders.get(&shader).cloned())
+}
+
+pub fn get_attached_shaders(program: UInt) -> Vec<UInt> {
+    with_state(|s| s.programs.get(&program).cloned().unwrap_or_default())
+}
+
+pub fn get_current_program() -> UInt {
+    with_state(|s| s.current_program)
+}
+
+pub fn get_bound_vertex_array() -> UInt {
+    with_state(|s| s.vertex_array)
+}
+
+pub fn get_bound_framebuffer() -> UInt {
+    with_state(|s| s.framebuffer)
+}
+
+pub fn get_bound_renderbuffer() -> UInt {
+    with_state(|s| s.renderbuffer)
+}
@@ -0,0 +1,800 @@
+use {
+    super::{
+        constants::{
+            BufferTarget, BufferUsage, FramebufferTarget, InternalFormat, ShaderType,
+            TextureTarget, CLAMP_TO_EDGE, COLOR_ATTACHMENT0, COMPILE_STATUS, DEPTH_ATTACHMENT,
+            DEPTH_STENCIL_ATTACHMENT, FRAMEBUFFER, FRAMEBUFFER_BINDING, FRAMEBUFFER_COMPLETE,
+            FRAMEBUFFER_INCOMPLETE_ATTACHMENT, FRAMEBUFFER_INCOMPLETE_DRAW_BUFFER,
+            FRAMEBUFFER_INCOMPLETE_LAYER_TARGETS, FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT,
+            FRAMEBUFFER_INCOMPLETE_MULTISAMPLE, FRAMEBUFFER_INCOMPLETE_READ_BUFFER,
+            FRAMEBUFFER_UNDEFINED, FRAMEBUFFER_UNSUPPORTED, INFO_LOG_LENGTH, LINEAR, LINK_STATUS,
+            NEAREST, NONE, RENDERBUFFER, RENDERBUFFER_BINDING, STENCIL_ATTACHMENT, TEXTURE_2D,
+            TEXTURE_BINDING_2D, TEXTURE_MAG_FILTER, TEXTURE_MIN_FILTER, TEXTURE_WRAP_S,
+            TEXTURE_WRAP_T,
+        },
+        error::{FramebufferError, GlError},
+        loader::Loader,
+        reflection::Reflection,
+        types::{Enumerated, SInt, SizeI, SizeIPtr, UInt},
+    },
+    std::{
+        mem::size_of_val,
+        os::raw::{c_char, c_void},
+        ptr::null,
+        sync::Arc,
+    },
+};
+
+pub struct Buffer {
+    loader: Arc<Loader>,
+    id: UInt,
+}
+
+impl Buffer {
+    pub fn new(loader: &Arc<Loader>) -> Self {
+        let mut id = 0;
+        loader.gen_buffers(1, &mut id);
+        Self {
+            loader: loader.clone(),
+            id,
+        }
+    }
+
+    pub fn get_id(&self) -> UInt {
+        self.id
+    }
+
+    pub fn bind(&self, target: BufferTarget) {
+        self.loader.bind_buffer(target.into(), self.id);
+    }
+
+    pub fn set_data<T>(&self, target: BufferTarget, data: &[T], usage: BufferUsage) {
+        self.bind(target);
+        self.loader.buffer_data(
+            target.into(),
+            size_of_val(data) as SizeIPtr,
+            data.as_ptr() as *const c_void,
+            usage.into(),
+        );
+    }
+}
+
+impl Drop for Buffer {
+    fn drop(&mut self) {
+        self.loader.delete_buffers(1, &self.id);
+    }
+}
+
+pub struct Texture {
+    loader: Arc<Loader>,
+    id: UInt,
+}
+
+impl Texture {
+    pub fn new(loader: &Arc<Loader>) -> Self {
+        let mut id = 0;
+        loader.gen_textures(1, &mut id);
+        Self {
+            loader: loader.clone(),
+            id,
+        }
+    }
+
+    pub fn get_id(&self) -> UInt {
+        self.id
+    }
+
+    pub fn bind(&self, target: TextureTarget) {
+        self.loader.bind_texture(target.into(), self.id);
+    }
+}
+
+impl Drop for Texture {
+    fn drop(&mut self) {
+        self.loader.delete_textures(1, &self.id);
+    }
+}
+
+pub struct Framebuffer {
+    loader: Arc<Loader>,
+    id: UInt,
+}
+
+impl Framebuffer {
+    pub fn new(loader: &Arc<Loader>) -> Result<Self, GlError> {
+        // Bind and delete are called without checks for the whole life of the framebuffer.
+        loader.gen_framebuffers.ok_or(GlError::FunctionNotFound("glGenFramebuffers"))?;
+        loader.bind_framebuffer.ok_or(GlError::FunctionNotFound("glBindFramebuffer"))?;
+        loader.delete_framebuffers.ok_or(GlError::FunctionNotFound("glDeleteFramebuffers"))?;
+        let mut id = 0;
+        loader.gen_framebuffers(1, &mut id);
+        Ok(Self {
+            loader: loader.clone(),
+            id,
+        })
+    }
+
+    pub fn get_id(&self) -> UInt {
+        self.id
+    }
+
+    pub fn bind(&self, target: FramebufferTarget) {
+        self.loader.bind_framebuffer(target.into(), self.id);
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        self.loader.delete_framebuffers(1, &self.id);
+    }
+}
+
+pub struct Renderbuffer {
+    loader: Arc<Loader>,
+    id: UInt,
+}
+
+impl Renderbuffer {
+    pub fn new(loader: &Arc<Loader>) -> Result<Self, GlError> {
+        loader.gen_renderbuffers.ok_or(GlError::FunctionNotFound("glGenRenderbuffers"))?;
+        loader.bind_renderbuffer.ok_or(GlError::FunctionNotFound("glBindRenderbuffer"))?;
+        loader.renderbuffer_storage.ok_or(GlError::FunctionNotFound("glRenderbufferStorage"))?;
+        loader.delete_renderbuffers.ok_or(GlError::FunctionNotFound("glDeleteRenderbuffers"))?;
+        let mut id = 0;
+        loader.gen_renderbuffers(1, &mut id);
+        Ok(Self {
+            loader: loader.clone(),
+            id,
+        })
+    }
+
+    pub fn get_id(&self) -> UInt {
+        self.id
+    }
+
+    pub fn bind(&self) {
+        self.loader.bind_renderbuffer(RENDERBUFFER, self.id);
+    }
+
+    pub fn set_storage(&self, format: InternalFormat, width: SizeI, height: SizeI) {
+        self.bind();
+        self.loader.renderbuffer_storage(RENDERBUFFER, format.into(), width, height);
+    }
+}
+
+impl Drop for Renderbuffer {
+    fn drop(&mut self) {
+        self.loader.delete_renderbuffers(1, &self.id);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Attachment {
+    Color(u32),
+    Depth,
+    Stencil,
+    DepthStencil,
+}
+
+impl From<Attachment> for Enumerated {
+    fn from(v: Attachment) -> Self {
+        match v {
+            Attachment::Color(i) => COLOR_ATTACHMENT0 + i,
+            Attachment::Depth => DEPTH_ATTACHMENT,
+            Attachment::Stencil => STENCIL_ATTACHMENT,
+            Attachment::DepthStencil => DEPTH_STENCIL_ATTACHMENT,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AttachmentKind {
+    Texture,
+    Renderbuffer,
+}
+
+pub enum AttachmentImage {
+    Texture(Texture),
+    Renderbuffer(Renderbuffer),
+}
+
+impl AttachmentImage {
+    pub fn get_id(&self) -> UInt {
+        match self {
+            AttachmentImage::Texture(t) => t.get_id(),
+            AttachmentImage::Renderbuffer(r) => r.get_id(),
+        }
+    }
+}
+
+pub struct RenderTarget {
+    framebuffer: Framebuffer,
+    width: SizeI,
+    height: SizeI,
+    attachments: Vec<(Attachment, InternalFormat, AttachmentImage)>,
+}
+
+impl RenderTarget {
+    pub fn get_framebuffer(&self) -> &Framebuffer {
+        &self.framebuffer
+    }
+
+    pub fn get_size(&self) -> (SizeI, SizeI) {
+        (self.width, self.height)
+    }
+
+    pub fn get_attachment(&self, attachment: Attachment) -> Option<&AttachmentImage> {
+        self.attachments
+            .iter()
+            .find(|a| a.0 == attachment)
+            .map(|a| &a.2)
+    }
+
+    pub fn get_format(&self, attachment: Attachment) -> Option<InternalFormat> {
+        self.attachments
+            .iter()
+            .find(|a| a.0 == attachment)
+            .map(|a| a.1)
+    }
+
+    pub fn get_texture(&self, attachment: Attachment) -> Option<&Texture> {
+        match self.get_attachment(attachment) {
+            Some(AttachmentImage::Texture(t)) => Some(t),
+            _ => None,
+        }
+    }
+
+    pub fn bind(&self, target: FramebufferTarget) {
+        self.framebuffer.bind(target);
+    }
+}
+
+pub struct FramebufferBuilder {
+    loader: Arc<Loader>,
+    width: SizeI,
+    height: SizeI,
+    attachments: Vec<(Attachment, InternalFormat, AttachmentKind)>,
+}
+
+impl FramebufferBuilder {
+    pub fn new(loader: &Arc<Loader>, width: SizeI, height: SizeI) -> Self {
+        Self {
+            loader: loader.clone(),
+            width,
+            height,
+            attachments: Vec::new(),
+        }
+    }
+
+    pub fn color(mut self, format: InternalFormat, kind: AttachmentKind) -> Self {
+        let index = self
+            .attachments
+            .iter()
+            .filter(|a| matches!(a.0, Attachment::Color(_)))
+            .count();
+        self.attachments
+            .push((Attachment::Color(index as u32), format, kind));
+        self
+    }
+
+    pub fn depth(self, format: InternalFormat, kind: AttachmentKind) -> Self {
+        self.attachment(Attachment::Depth, format, kind)
+    }
+
+    pub fn stencil(self, format: InternalFormat, kind: AttachmentKind) -> Self {
+        self.attachment(Attachment::Stencil, format, kind)
+    }
+
+    pub fn depth_stencil(self, format: InternalFormat, kind: AttachmentKind) -> Self {
+        self.attachment(Attachment::DepthStencil, format, kind)
+    }
+
+    fn attachment(
+        mut self,
+        attachment: Attachment,
+        format: InternalFormat,
+        kind: AttachmentKind,
+    ) -> Self {
+        self.attachments.retain(|a| a.0 != attachment);
+        self.attachments.push((attachment, format, kind));
+        self
+    }
+
+    pub fn build(self) -> Result<RenderTarget, GlError> {
+        for &(attachment, format, _) in &self.attachments {
+            let valid = match attachment {
+                Attachment::Color(_) => !format.has_depth() && !format.has_stencil(),
+                Attachment::Depth => format.has_depth(),
+                Attachment::Stencil => format.has_stencil(),
+                Attachment::DepthStencil => format.has_depth() && format.has_stencil(),
+            };
+            if !valid {
+                return Err(GlError::IncompleteFramebuffer(
+                    FramebufferError::InvalidFormat(attachment, format),
+                ));
+            }
+        }
+        self.loader
+            .framebuffer_texture2d
+            .ok_or(GlError::FunctionNotFound("glFramebufferTexture2D"))?;
+        self.loader
+            .framebuffer_renderbuffer
+            .ok_or(GlError::FunctionNotFound("glFramebufferRenderbuffer"))?;
+        self.loader
+            .check_framebuffer_status
+            .ok_or(GlError::FunctionNotFound("glCheckFramebufferStatus"))?;
+        self.loader
+            .bind_renderbuffer
+            .ok_or(GlError::FunctionNotFound("glBindRenderbuffer"))?;
+        let check = |target| self.loader.check_framebuffer_status(target);
+        let get = |name| {
+            let mut value = 0;
+            self.loader.get_integer_v(name, &mut value);
+            value as UInt
+        };
+        let previous = (
+            get(FRAMEBUFFER_BINDING),
+            get(TEXTURE_BINDING_2D),
+            get(RENDERBUFFER_BINDING),
+        );
+
+        // Only the creation of the objects can fail, so it is done before any binding changes.
+        let framebuffer = Framebuffer::new(&self.loader)?;
+        let mut renderbuffers = Vec::new();
+        for &(_, _, kind) in &self.attachments {
+            if kind == AttachmentKind::Renderbuffer {
+                renderbuffers.push(Renderbuffer::new(&self.loader)?);
+            }
+        }
+        let mut renderbuffers = renderbuffers.into_iter();
+        framebuffer.bind(FramebufferTarget::Framebuffer);
+        let mut attachments = Vec::new();
+        for &(attachment, format, kind) in &self.attachments {
+            let image = match kind {
+                AttachmentKind::Texture => {
+                    let texture = Texture::new(&self.loader);
+                    texture.bind(TextureTarget::Texture2D);
+                    let filter = if format.is_integer() || format.has_depth() {
+                        NEAREST as SInt
+                    } else {
+                        LINEAR as SInt
+                    };
+                    self.loader.tex_parameter_i(TEXTURE_2D, TEXTURE_MIN_FILTER, filter);
+                    self.loader.tex_parameter_i(TEXTURE_2D, TEXTURE_MAG_FILTER, filter);
+                    let wrap = CLAMP_TO_EDGE as SInt;
+                    self.loader.tex_parameter_i(TEXTURE_2D, TEXTURE_WRAP_S, wrap);
+                    self.loader.tex_parameter_i(TEXTURE_2D, TEXTURE_WRAP_T, wrap);
+                    let (transfer_format, transfer_type) = format.get_transfer();
+                    self.loader.tex_image_2d(
+                        TEXTURE_2D,
+                        0,
+                        Enumerated::from(format) as SInt,
+                        self.width,
+                        self.height,
+                        0,
+                        transfer_format,
+                        transfer_type,
+                        null(),
+                    );
+                    AttachmentImage::Texture(texture)
+                }
+                AttachmentKind::Renderbuffer => {
+                    let storage = renderbuffers.next().unwrap();
+                    storage.set_storage(format, self.width, self.height);
+                    AttachmentImage::Renderbuffer(storage)
+                }
+            };
+            attachments.push((attachment, format, image));
+        }
+        let attach = |attachment: Attachment, image: &AttachmentImage, id: UInt| match image {
+            AttachmentImage::Texture(_) => {
+                self.loader
+                    .framebuffer_texture2d(FRAMEBUFFER, attachment.into(), TEXTURE_2D, id, 0)
+            }
+            AttachmentImage::Renderbuffer(_) => {
+                self.loader
+                    .framebuffer_renderbuffer(FRAMEBUFFER, attachment.into(), RENDERBUFFER, id)
+            }
+        };
+        let draw = |colors: &[Enumerated]| {
+            if self.loader.draw_buffers.is_some() {
+                if colors.is_empty() {
+                    self.loader.draw_buffers(1, &NONE);
+                } else {
+                    self.loader.draw_buffers(colors.len() as SizeI, colors.as_ptr());
+                }
+            }
+            if self.loader.read_buffer.is_some() {
+                self.loader.read_buffer(colors.iter().cloned().find(|&c| c != NONE).unwrap_or(NONE));
+            }
+        };
+        for (attachment, _, image) in &attachments {
+            attach(*attachment, image, image.get_id());
+        }
+        let colors: Vec<Enumerated> = attachments
+            .iter()
+            .filter(|a| matches!(a.0, Attachment::Color(_)))
+            .map(|a| a.0.into())
+            .collect();
+        draw(&colors);
+
+        let status = check(FRAMEBUFFER);
+        // The status does not say which attachment is at fault, so they are attached again one by
+        // one and the first that brings the status back is blamed, which also catches combinations.
+        let culprit = || {
+            for (a, _, i) in &attachments {
+                attach(*a, i, 0);
+            }
+            let mut buffers = Vec::new();
+            for (attachment, _, image) in &attachments {
+                attach(*attachment, image, image.get_id());
+                if let Attachment::Color(i) = *attachment {
+                    buffers.resize(i as usize, NONE);
+                    buffers.push((*attachment).into());
+                }
+                draw(&buffers);
+                if check(FRAMEBUFFER) == status {
+                    return Some(*attachment);
+                }
+            }
+            None
+        };
+        let result = match status {
+            FRAMEBUFFER_COMPLETE => Ok(()),
+            FRAMEBUFFER_UNDEFINED => Err(FramebufferError::Undefined),
+            FRAMEBUFFER_INCOMPLETE_ATTACHMENT => {
+                Err(FramebufferError::IncompleteAttachment(culprit()))
+            }
+            FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT => Err(FramebufferError::MissingAttachment),
+            FRAMEBUFFER_INCOMPLETE_DRAW_BUFFER => {
+                Err(FramebufferError::IncompleteDrawBuffer(culprit()))
+            }
+            FRAMEBUFFER_INCOMPLETE_READ_BUFFER => {
+                Err(FramebufferError::IncompleteReadBuffer(culprit()))
+            }
+            FRAMEBUFFER_UNSUPPORTED => Err(FramebufferError::Unsupported(culprit())),
+            FRAMEBUFFER_INCOMPLETE_MULTISAMPLE => {
+                Err(FramebufferError::IncompleteMultisample(culprit()))
+            }
+            FRAMEBUFFER_INCOMPLETE_LAYER_TARGETS => {
+                Err(FramebufferError::IncompleteLayerTargets(culprit()))
+            }
+            s => Err(FramebufferError::Unknown(s)),
+        };
+
+        self.loader.bind_framebuffer(FRAMEBUFFER, previous.0);
+        self.loader.bind_texture(TEXTURE_2D, previous.1);
+        self.loader.bind_renderbuffer(RENDERBUFFER, previous.2);
+        result.map_err(GlError::IncompleteFramebuffer)?;
+        Ok(RenderTarget {
+            framebuffer,
+            width: self.width,
+            height: self.height,
+            attachments,
+        })
+    }
+}
+
+pub struct VertexArray {
+    loader: Arc<Loader>,
+    id: UInt,
+}
+
+impl VertexArray {
+    pub fn new(loader: &Arc<Loader>) -> Result<Self, GlError> {
+        loader.gen_vertex_arrays.ok_or(GlError::FunctionNotFound("glGenVertexArrays"))?;
+        loader.bind_vertex_array.ok_or(GlError::FunctionNotFound("glBindVertexArray"))?;
+        loader.delete_vertex_arrays.ok_or(GlError::FunctionNotFound("glDeleteVertexArrays"))?;
+        let mut id = 0;
+        loader.gen_vertex_arrays(1, &mut id);
+        Ok(Self {
+            loader: loader.clone(),
+            id,
+        })
+    }
+
+    pub fn get_id(&self) -> UInt {
+        self.id
+    }
+
+    pub fn bind(&self) {
+        self.loader.bind_vertex_array(self.id);
+    }
+}
+
+impl Drop for VertexArray {
+    fn drop(&mut self) {
+        self.loader.delete_vertex_arrays(1, &self.id);
+    }
+}
+
+fn get_info_log(
+    id: UInt,
+    get_iv: impl Fn(UInt, Enumerated, *mut SInt),
+    get_log: impl Fn(UInt, SizeI, *mut SizeI, *mut c_char),
+) -> String {
+    let mut length = 0;
+    get_iv(id, INFO_LOG_LENGTH, &mut length);
+    if length <= 0 {
+        return String::new();
+    }
+    let mut log = vec![0u8; length as usize];
+    let mut written = 0;
+    get_log(
+        id,
+        length as SizeI,
+        &mut written,
+        log.as_mut_ptr() as *mut c_char,
+    );
+    log.truncate(written as usize);
+    String::from_utf8_lossy(&log).into_owned()
+}
+
+pub struct Shader {
+    loader: Arc<Loader>,
+    id: UInt,
+    shader_type: ShaderType,
+}
+
+impl Shader {
+    pub fn new(loader: &Arc<Loader>, shader_type: ShaderType) -> Self {
+        Self {
+            loader: loader.clone(),
+            id: loader.create_shader(shader_type.into()),
+            shader_type,
+        }
+    }
+
+    pub fn get_id(&self) -> UInt {
+        self.id
+    }
+
+    pub fn get_type(&self) -> ShaderType {
+        self.shader_type
+    }
+
+    // The sources are passed with their lengths, so they need no NUL terminator.
+    pub fn set_sources(&self, sources: &[&str]) {
+        let pointers: Vec<*const c_char> =
+            sources.iter().map(|s| s.as_ptr() as *const c_char).collect();
+        let lengths: Vec<SInt> = sources.iter().map(|s| s.len() as SInt).collect();
+        self.loader.shader_source(
+            self.id,
+            pointers.len() as SizeI,
+            pointers.as_ptr(),
+            lengths.as_ptr(),
+        );
+    }
+
+    pub fn compile(&self) -> bool {
+        self.loader.compile_shader(self.id);
+        let mut status = 0;
+        self.loader.get_shader_iv(self.id, COMPILE_STATUS, &mut status);
+        status != 0
+    }
+
+    pub fn get_info_log(&self) -> String {
+        get_info_log(
+            self.id,
+            |id, pname, params| self.loader.get_shader_iv(id, pname, params),
+            |id, size, length, log| self.loader.get_shader_info_log(id, size, length, log),
+        )
+    }
+}
+
+impl Drop for Shader {
+    fn drop(&mut self) {
+        self.loader.delete_shader(self.id);
+    }
+}
+
+pub struct Program {
+    loader: Arc<Loader>,
+    id: UInt,
+}
+
+impl Program {
+    pub fn new(loader: &Arc<Loader>) -> Self {
+        Self {
+            loader: loader.clone(),
+            id: loader.create_program(),
+        }
+    }
+
+    pub fn get_id(&self) -> UInt {
+        self.id
+    }
+
+    pub fn attach(&self, shader: &Shader) {
+        self.loader.attach_shader(self.id, shader.id);
+    }
+
+    pub fn detach(&self, shader: &Shader) {
+        self.loader.detach_shader(self.id, shader.id);
+    }
+
+    pub fn link(&self) -> bool {
+        self.loader.link_program(self.id);
+        let mut status = 0;
+        self.loader.get_program_iv(self.id, LINK_STATUS, &mut status);
+        status != 0
+    }
+
+    pub fn get_info_log(&self) -> String {
+        get_info_log(
+            self.id,
+            |id, pname, params| self.loader.get_program_iv(id, pname, params),
+            |id, size, length, log| self.loader.get_program_info_log(id, size, length, log),
+        )
+    }
+
+    pub fn use_program(&self) {
+        self.loader.use_program(self.id);
+    }
+
+    pub fn reflect(&self) -> Result<Reflection, GlError> {
+        Reflection::new(&self.loader, self.id)
+    }
+}
+
+impl Drop for Program {
+    fn drop(&mut self) {
+        self.loader.delete_program(self.id);
+    }
+}
+
+#[cfg(all(test, feature = "gl-mock"))]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            constants::{ARRAY_BUFFER, INVALID_ENUM, INVALID_VALUE, NO_ERROR, TEXTURE0},
+            loader::Loader,
+            mock,
+        },
+    };
+
+    #[test]
+    fn buffer_uploads_and_deletes() {
+        let loader = mock::new_loader();
+        let buffer = Buffer::new(&loader);
+        let id = buffer.get_id();
+        buffer.set_data(BufferTarget::Array, &[1u8, 2, 3, 4], BufferUsage::StaticDraw);
+        assert_eq!(mock::get_bound_buffer(ARRAY_BUFFER), id);
+        assert_eq!(mock::get_buffer_data(id), Some(vec![1, 2, 3, 4]));
+        assert_eq!(mock::get_calls_of("glBufferData")[0].args[1], 4);
+        drop(buffer);
+        assert_eq!(mock::get_calls_of("glDeleteBuffers").len(), 1);
+        assert_eq!(mock::get_buffer_data(id), None);
+    }
+
+    #[test]
+    fn mapping_past_the_end_fails() {
+        let loader = mock::new_loader();
+        let buffer = Buffer::new(&loader);
+        buffer.set_data(BufferTarget::Array, &[0u8; 4], BufferUsage::StaticDraw);
+        // Through the raw pointers, so gl-check does not take the error first.
+        let map = loader.map_buffer_range.unwrap();
+        assert!(!map(ARRAY_BUFFER, 2, 2, 0).is_null());
+        assert_eq!((loader.get_error)(), NO_ERROR);
+        assert!(map(ARRAY_BUFFER, 2, 4, 0).is_null());
+        assert_eq!((loader.get_error)(), INVALID_VALUE);
+    }
+
+    #[test]
+    fn writing_past_the_end_fails() {
+        let loader = mock::new_loader();
+        let buffer = Buffer::new(&loader);
+        let id = buffer.get_id();
+        buffer.set_data(BufferTarget::Array, &[0u8; 4], BufferUsage::StaticDraw);
+        let data = [1u8, 2, 3];
+        (loader.buffer_sub_data)(ARRAY_BUFFER, 1, 3, data.as_ptr() as *const c_void);
+        assert_eq!((loader.get_error)(), NO_ERROR);
+        (loader.buffer_sub_data)(ARRAY_BUFFER, 2, 3, data.as_ptr() as *const c_void);
+        assert_eq!((loader.get_error)(), INVALID_VALUE);
+        assert_eq!(mock::get_buffer_data(id), Some(vec![0, 1, 2, 3]));
+    }
+
+    #[test]
+    fn active_texture_below_texture0_fails() {
+        let loader = mock::new_loader();
+        (loader.active_texture)(TEXTURE0 + 1);
+        assert_eq!((loader.get_error)(), NO_ERROR);
+        (loader.active_texture)(TEXTURE0 - 1);
+        assert_eq!((loader.get_error)(), INVALID_ENUM);
+        let texture = Texture::new(&loader);
+        texture.bind(TextureTarget::Texture2D);
+        assert_eq!(mock::get_bound_texture(1, TEXTURE_2D), texture.get_id());
+    }
+
+    #[test]
+    fn texture_binds_to_the_active_unit() {
+        let loader = mock::new_loader();
+        let texture = Texture::new(&loader);
+        texture.bind(TextureTarget::Texture2D);
+        assert_eq!(mock::get_bound_texture(0, TEXTURE_2D), texture.get_id());
+        drop(texture);
+        assert_eq!(mock::get_bound_texture(0, TEXTURE_2D), 0);
+    }
+
+    #[test]
+    fn vertex_array_binds_and_deletes() {
+        let loader = mock::new_loader();
+        let vertex_array = VertexArray::new(&loader).unwrap();
+        vertex_array.bind();
+        assert_eq!(mock::get_bound_vertex_array(), vertex_array.get_id());
+        drop(vertex_array);
+        assert_eq!(mock::get_bound_vertex_array(), 0);
+        assert_eq!(mock::get_calls_of("glDeleteVertexArrays").len(), 1);
+    }
+
+    #[test]
+    fn framebuffer_builder_restores_bindings() {
+        let loader = mock::new_loader();
+        let target = FramebufferBuilder::new(&loader, 4, 4)
+            .color(InternalFormat::Rgba8, AttachmentKind::Texture)
+            .depth(InternalFormat::DepthComponent24, AttachmentKind::Renderbuffer)
+            .build()
+            .unwrap();
+        assert!(target.get_texture(Attachment::Color(0)).is_some());
+        assert_eq!(mock::get_calls_of("glFramebufferTexture2D").len(), 1);
+        assert_eq!(mock::get_calls_of("glFramebufferRenderbuffer").len(), 1);
+        assert_eq!(mock::get_bound_framebuffer(), 0);
+        assert_eq!(mock::get_bound_renderbuffer(), 0);
+    }
+
+    #[test]
+    fn framebuffer_builder_rejects_formats_for_the_wrong_slot() {
+        let loader = mock::new_loader();
+        let result = FramebufferBuilder::new(&loader, 4, 4)
+            .color(InternalFormat::Rgba8, AttachmentKind::Texture)
+            .depth(InternalFormat::Rgba8, AttachmentKind::Renderbuffer)
+            .build();
+        assert_eq!(
+            result.err(),
+            Some(GlError::IncompleteFramebuffer(
+                FramebufferError::InvalidFormat(Attachment::Depth, InternalFormat::Rgba8)
+            ))
+        );
+        let result = FramebufferBuilder::new(&loader, 4, 4)
+            .color(InternalFormat::DepthComponent24, AttachmentKind::Texture)
+            .build();
+        assert!(result.is_err());
+        assert!(mock::get_calls_of("glGenFramebuffers").is_empty());
+    }
+
+    #[test]
+    fn framebuffer_builder_names_the_incomplete_attachment() {
+        let loader = mock::new_loader();
+        mock::set_incomplete_attachment(DEPTH_ATTACHMENT, FRAMEBUFFER_INCOMPLETE_ATTACHMENT);
+        let result = FramebufferBuilder::new(&loader, 4, 4)
+            .color(InternalFormat::Rgba8, AttachmentKind::Texture)
+            .depth(InternalFormat::DepthComponent24, AttachmentKind::Renderbuffer)
+            .build();
+        assert_eq!(
+            result.err(),
+            Some(GlError::IncompleteFramebuffer(
+                FramebufferError::IncompleteAttachment(Some(Attachment::Depth))
+            ))
+        );
+        assert_eq!(mock::get_bound_framebuffer(), 0);
+        assert_eq!(mock::get_bound_texture(0, TEXTURE_2D), 0);
+        assert_eq!(mock::get_bound_renderbuffer(), 0);
+    }
+
+    #[test]
+    #[allow(clippy::arc_with_non_send_sync)]
+    fn framebuffer_builder_fails_before_binding() {
+        mock::reset();
+        let mut loader = Loader::new_mock();
+        loader.renderbuffer_storage = None;
+        let loader = Arc::new(loader);
+        let result = FramebufferBuilder::new(&loader, 4, 4)
+            .color(InternalFormat::Rgba8, AttachmentKind::Texture)
+            .depth(InternalFormat::DepthComponent24, AttachmentKind::Renderbuffer)
+            .build();
+        assert_eq!(result.err(), Some(GlError::FunctionNotFound("glRenderbufferStorage")));
+        assert!(mock::get_calls_of("glBindFramebuffer").is_empty());
+        assert_eq!(mock::get_calls_of("glDeleteFramebuffers").len(), 1);
+    }
+}
@@ -0,0 +1,19 @@
+use std::os::raw::{c_ulong, c_void};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Default,
+    X11 {
+        display: *mut c_void,
+        window: c_ulong,
+    },
+    Wayland {
+        display: *mut c_void,
+        window: *mut c_void,
+    },
+    Gbm {
+        device: *mut c_void,
+        surface: *mut c_void,
+    },
+    Surfaceless,
+}
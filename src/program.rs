@@ -0,0 +1,382 @@
+use {
+    super::{
+        constants::ShaderType,
+        loader::Loader,
+        objects::{Program, Shader},
+        types::UInt,
+    },
+    std::{error::Error, ffi::CString, fmt, sync::Arc},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InfoLogEntry {
+    pub stage: Option<ShaderType>,
+    pub severity: Severity,
+    pub source: Option<usize>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub text: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum ProgramError {
+    Compile {
+        stage: ShaderType,
+        log: String,
+        entries: Vec<InfoLogEntry>,
+    },
+    Link {
+        log: String,
+        entries: Vec<InfoLogEntry>,
+    },
+    InvalidName(String),
+}
+
+impl ProgramError {
+    pub fn get_entries(&self) -> &[InfoLogEntry] {
+        match self {
+            ProgramError::Compile { entries, .. } | ProgramError::Link { entries, .. } => entries,
+            ProgramError::InvalidName(_) => &[],
+        }
+    }
+}
+
+impl fmt::Display for ProgramError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProgramError::Compile { stage, log, .. } => {
+                write!(f, "{:?} shader compilation failed: {}", stage, log)
+            }
+            ProgramError::Link { log, .. } => write!(f, "program linking failed: {}", log),
+            ProgramError::InvalidName(name) => {
+                write!(f, "attribute name {:?} contains a NUL byte", name)
+            }
+        }
+    }
+}
+
+impl Error for ProgramError {}
+
+pub struct ProgramBuilder {
+    loader: Arc<Loader>,
+    stages: Vec<(ShaderType, Vec<String>)>,
+    attributes: Vec<(UInt, String)>,
+}
+
+impl ProgramBuilder {
+    pub fn new(loader: &Arc<Loader>) -> Self {
+        Self {
+            loader: loader.clone(),
+            stages: Vec::new(),
+            attributes: Vec::new(),
+        }
+    }
+
+    pub fn stage(mut self, shader_type: ShaderType, source: &str) -> Self {
+        self.stages.push((shader_type, vec![source.to_string()]));
+        self
+    }
+
+    pub fn stage_sources(mut self, shader_type: ShaderType, sources: &[&str]) -> Self {
+        self.stages.push((
+            shader_type,
+            sources.iter().map(|s| (*s).to_string()).collect(),
+        ));
+        self
+    }
+
+    pub fn attribute(mut self, index: UInt, name: &str) -> Self {
+        self.attributes.push((index, name.to_string()));
+        self
+    }
+
+    pub fn build(self) -> Result<Program, ProgramError> {
+        let attributes = self
+            .attributes
+            .iter()
+            .map(|(index, name)| match CString::new(name.as_str()) {
+                Ok(c) => Ok((*index, c)),
+                Err(_) => Err(ProgramError::InvalidName(name.clone())),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let program = Program::new(&self.loader);
+        let mut shaders = Vec::with_capacity(self.stages.len());
+        for (stage, sources) in &self.stages {
+            let sources: Vec<&str> = sources.iter().map(|s| s.as_str()).collect();
+            let shader = Shader::new(&self.loader, *stage);
+            shader.set_sources(&sources);
+            if !shader.compile() {
+                let log = shader.get_info_log();
+                return Err(ProgramError::Compile {
+                    stage: *stage,
+                    entries: parse_info_log(Some(*stage), &log, &sources),
+                    log,
+                });
+            }
+            program.attach(&shader);
+            shaders.push(shader);
+        }
+        for (index, name) in &attributes {
+            self.loader
+                .bind_attrib_location(program.get_id(), *index, name.as_ptr());
+        }
+        let linked = program.link();
+        for shader in &shaders {
+            program.detach(shader);
+        }
+        if !linked {
+            let log = program.get_info_log();
+            return Err(ProgramError::Link {
+                entries: parse_info_log(None, &log, &[]),
+                log,
+            });
+        }
+        Ok(program)
+    }
+}
+
+fn number(s: &str) -> Option<(usize, &str)> {
+    let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    if end == 0 {
+        return None;
+    }
+    Some((s[..end].parse().ok()?, &s[end..]))
+}
+
+// Mesa "0:12(5):", NVIDIA "0(12) :", ANGLE, AMD and most mobile drivers "0:12:", the first number is the
+// source string.
+fn parse_location(s: &str) -> Option<(usize, usize, Option<usize>, &str)> {
+    let (source, rest) = number(s)?;
+    let (line, column, rest) = if let Some(rest) = rest.strip_prefix(':') {
+        let (line, rest) = number(rest)?;
+        match rest.strip_prefix('(') {
+            Some(rest) => {
+                let (column, rest) = number(rest)?;
+                (line, Some(column), rest.strip_prefix(')')?)
+            }
+            None => (line, None, rest),
+        }
+    } else {
+        let (line, rest) = number(rest.strip_prefix('(')?)?;
+        (line, None, rest.strip_prefix(')')?)
+    };
+    Some((
+        source,
+        line,
+        column,
+        rest.trim_start().strip_prefix(':')?.trim(),
+    ))
+}
+
+fn parse_severity(s: &str) -> (Option<Severity>, &str) {
+    let end = s
+        .find(|c: char| c == ':' || c.is_whitespace())
+        .unwrap_or(s.len());
+    let severity = match s[..end].to_lowercase().as_str() {
+        "error" => Severity::Error,
+        "warning" => Severity::Warning,
+        "info" | "note" => Severity::Info,
+        _ => return (None, s),
+    };
+    let mut rest = &s[end..];
+    if let Some(colon) = rest.find(':') {
+        if !rest[..colon].trim().contains(char::is_whitespace) {
+            rest = &rest[colon + 1..];
+        }
+    }
+    (Some(severity), rest.trim())
+}
+
+fn locate(sources: &[&str], source: usize, line: usize) -> Option<(usize, usize, String)> {
+    // Drivers count lines through every string from string 0, unless a #line directive or the driver
+    // numbers the strings on their own.
+    if source > 0 {
+        let text = sources.get(source)?.split('\n').nth(line.checked_sub(1)?)?;
+        return Some((source, line, text.to_string()));
+    }
+    let joined = sources.concat();
+    let offset = if line <= 1 {
+        0
+    } else {
+        joined.match_indices('\n').nth(line - 2)?.0 + 1
+    };
+    let text = joined[offset..].split('\n').next()?.to_string();
+    let mut start = 0;
+    for (i, s) in sources.iter().enumerate() {
+        if offset < start + s.len() || i + 1 == sources.len() {
+            let local = s[..(offset - start).min(s.len())].matches('\n').count() + 1;
+            return Some((i, local, text));
+        }
+        start += s.len();
+    }
+    None
+}
+
+pub fn parse_info_log(stage: Option<ShaderType>, log: &str, sources: &[&str]) -> Vec<InfoLogEntry> {
+    let mut entries = Vec::new();
+    for l in log.lines() {
+        let l = l.trim();
+        if l.is_empty() {
+            continue;
+        }
+        let (severity, rest) = parse_severity(l);
+        let (source, line, column, rest) = match parse_location(rest) {
+            Some((source, line, column, rest)) => (Some(source), Some(line), column, rest),
+            None => (None, None, None, rest),
+        };
+        let (severity, message) = match severity {
+            Some(s) => (s, rest),
+            None => match parse_severity(rest) {
+                (Some(s), m) => (s, m),
+                (None, m) => (Severity::Info, m),
+            },
+        };
+        let location = source
+            .zip(line)
+            .and_then(|(source, line)| locate(sources, source, line));
+        entries.push(InfoLogEntry {
+            stage,
+            severity,
+            source: location.as_ref().map_or(source, |l| Some(l.0)),
+            line: location.as_ref().map_or(line, |l| Some(l.1)),
+            column,
+            text: location.map(|l| l.2),
+            message: message.to_string(),
+        });
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "gl-mock")]
+    use crate::{constants::VERTEX_SHADER, mock};
+
+    fn parse_one(log: &str, sources: &[&str]) -> InfoLogEntry {
+        let entries = parse_info_log(Some(ShaderType::Fragment), log, sources);
+        assert_eq!(entries.len(), 1);
+        entries.into_iter().next().unwrap()
+    }
+
+    #[test]
+    fn parses_mesa_logs() {
+        let e = parse_one(
+            "0:2(5): error: `foo' undeclared",
+            &["void main() {\n  foo;\n}\n"],
+        );
+        assert_eq!(e.severity, Severity::Error);
+        assert_eq!((e.source, e.line, e.column), (Some(0), Some(2), Some(5)));
+        assert_eq!(e.text.as_deref(), Some("  foo;"));
+        assert_eq!(e.message, "`foo' undeclared");
+    }
+
+    #[test]
+    fn parses_nvidia_logs() {
+        let e = parse_one(
+            "0(3) : warning C7022: unrecognized profile specifier",
+            &["#version 330\n", "void main() {\n}\n"],
+        );
+        assert_eq!(e.severity, Severity::Warning);
+        // Line 3 of all strings together is the second line of the second string.
+        assert_eq!((e.source, e.line, e.column), (Some(1), Some(2), None));
+        assert_eq!(e.text.as_deref(), Some("}"));
+        assert_eq!(e.message, "unrecognized profile specifier");
+    }
+
+    #[test]
+    fn parses_angle_logs() {
+        let e = parse_one("ERROR: 0:1: 'foo' : undeclared identifier", &["foo;\n"]);
+        assert_eq!(e.severity, Severity::Error);
+        assert_eq!((e.source, e.line, e.column), (Some(0), Some(1), None));
+        assert_eq!(e.text.as_deref(), Some("foo;"));
+        assert_eq!(e.message, "'foo' : undeclared identifier");
+    }
+
+    #[test]
+    fn uses_the_source_string_index() {
+        let sources = ["#version 300 es\n", "a;\nb;\n", "c;\nd;\n"];
+        let e = parse_one("ERROR: 2:2: 'd' : syntax error", &sources);
+        assert_eq!((e.source, e.line), (Some(2), Some(2)));
+        assert_eq!(e.text.as_deref(), Some("d;"));
+        // A string the shader does not have keeps the driver's numbers.
+        let e = parse_one("ERROR: 5:1: 'e' : syntax error", &sources);
+        assert_eq!((e.source, e.line, e.text), (Some(5), Some(1), None));
+    }
+
+    #[cfg(feature = "gl-mock")]
+    #[test]
+    fn builder_compiles_links_and_detaches() {
+        let loader = mock::new_loader();
+        let program = ProgramBuilder::new(&loader)
+            .stage_sources(
+                ShaderType::Vertex,
+                &["#version 300 es\n", "void main() {}\n"],
+            )
+            .stage(ShaderType::Fragment, "void main() {}\n")
+            .attribute(2, "position")
+            .build()
+            .unwrap();
+        let id = program.get_id();
+        let created = mock::get_calls_of("glCreateShader");
+        assert_eq!(created.len(), 2);
+        assert_eq!(created[0].args[0], VERTEX_SHADER as u64);
+        let sources = mock::get_calls_of("glShaderSource");
+        assert_eq!(sources[0].args[1], 2);
+        assert_eq!(mock::get_calls_of("glCompileShader").len(), 2);
+        assert_eq!(mock::get_calls_of("glAttachShader").len(), 2);
+        let bound = mock::get_calls_of("glBindAttribLocation");
+        assert_eq!(bound[0].args[..2], [id as u64, 2]);
+        assert_eq!(mock::get_calls_of("glLinkProgram")[0].args[0], id as u64);
+        assert!(mock::get_attached_shaders(id).is_empty());
+        // The shaders are no longer needed once the program is linked.
+        assert_eq!(mock::get_calls_of("glDeleteShader").len(), 2);
+        program.use_program();
+        assert_eq!(mock::get_current_program(), id);
+        drop(program);
+        assert_eq!(mock::get_calls_of("glDeleteProgram")[0].args[0], id as u64);
+    }
+
+    #[cfg(feature = "gl-mock")]
+    #[test]
+    fn attribute_names_with_nul_fail_before_compiling() {
+        let loader = mock::new_loader();
+        let result = ProgramBuilder::new(&loader)
+            .stage(ShaderType::Vertex, "void main() {}\n")
+            .attribute(0, "posi\0tion")
+            .build();
+        assert!(matches!(result, Err(ProgramError::InvalidName(_))));
+        assert!(mock::get_calls_of("glCreateProgram").is_empty());
+    }
+
+    #[cfg(feature = "gl-mock")]
+    #[test]
+    fn shader_keeps_every_source_string() {
+        let loader = mock::new_loader();
+        let shader = Shader::new(&loader, ShaderType::Fragment);
+        shader.set_sources(&["void ", "main() {}"]);
+        let stored = mock::get_shader(shader.get_id()).unwrap();
+        assert_eq!(stored.source, "void main() {}");
+        assert!(shader.compile());
+        assert!(shader.get_info_log().is_empty());
+    }
+
+    #[cfg(feature = "gl-mock")]
+    #[test]
+    fn shader_sources_are_passed_unchanged() {
+        let loader = mock::new_loader();
+        let shader = Shader::new(&loader, ShaderType::Fragment);
+        let source = "void main() {}\0// tail";
+        shader.set_sources(&[&source[..5], &source[5..]]);
+        let stored = mock::get_shader(shader.get_id()).unwrap();
+        assert_eq!(stored.source, source);
+    }
+}
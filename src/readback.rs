@@ -0,0 +1,368 @@
+use {
+    super::{
+        config::Api,
+        constants::{
+            BufferTarget, BufferUsage, InternalFormat, ALREADY_SIGNALED, CONDITION_SATISFIED,
+            FLOAT, HALF_FLOAT, IMPLEMENTATION_COLOR_READ_FORMAT, IMPLEMENTATION_COLOR_READ_TYPE,
+            INT, MAP_READ_BIT, PACK_ALIGNMENT, PIXEL_PACK_BUFFER_BINDING, RED_INTEGER, RGBA,
+            RGBA_INTEGER, RGB_INTEGER, RG_INTEGER, SYNC_FLUSH_COMMANDS_BIT,
+            SYNC_GPU_COMMANDS_COMPLETE, TIMEOUT_IGNORED, UNSIGNED_BYTE, UNSIGNED_INT,
+            UNSIGNED_INT_10F_11F_11F_REV, UNSIGNED_SHORT, VERSION, WAIT_FAILED,
+        },
+        error::GlError,
+        info::parse_version,
+        loader::Loader,
+        objects::Buffer,
+        types::{SizeIPtr, SyncObject, UInt},
+    },
+    crate::window::log::log_e,
+    std::{
+        ffi::CStr,
+        os::raw::{c_char, c_void},
+        ptr::{copy_nonoverlapping, null, null_mut},
+        sync::Arc,
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    pub fn new(x: i32, y: i32, width: u32, height: u32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn get_byte_size(&self, format: InternalFormat) -> usize {
+        self.width as usize * self.height as usize * format.get_pixel_size()
+    }
+}
+
+fn get_version(loader: &Loader) -> Option<(Api, (u8, u8))> {
+    let version = loader.get_string(VERSION);
+    if version.is_null() {
+        return None;
+    }
+    let version = unsafe { CStr::from_ptr(version as *const c_char) }.to_string_lossy();
+    Some(parse_version(&version))
+}
+
+// Desktop GL has the pixel pack buffer binding since 2.1, GLES only from 3.0.
+fn has_pack_binding(loader: &Loader) -> bool {
+    match get_version(loader) {
+        Some((Api::Gles, (major, _))) => {
+            major >= 3 || loader.has_extension("GL_NV_pixel_buffer_object")
+        }
+        _ => true,
+    }
+}
+
+fn has_pixel_buffers(loader: &Loader) -> bool {
+    has_pack_binding(loader)
+        && loader.fence_sync.is_some()
+        && loader.client_wait_sync.is_some()
+        && loader.delete_sync.is_some()
+        && loader.map_buffer_range.is_some()
+        && loader.unmap_buffer.is_some()
+}
+
+// GLES reads one pair that follows the kind of the read buffer and one chosen by the driver, the
+// kind is told by the driver's pair.
+fn check_format(loader: &Loader, format: InternalFormat) -> Result<(), GlError> {
+    match get_version(loader) {
+        Some((Api::Gles, _)) => (),
+        _ => return Ok(()),
+    }
+    let mut implementation = (0, 0);
+    loader.get_integer_v(IMPLEMENTATION_COLOR_READ_FORMAT, &mut implementation.0);
+    loader.get_integer_v(IMPLEMENTATION_COLOR_READ_TYPE, &mut implementation.1);
+    let implementation = (implementation.0 as UInt, implementation.1 as UInt);
+    let guaranteed = match implementation {
+        (RED_INTEGER, t) | (RG_INTEGER, t) | (RGB_INTEGER, t) | (RGBA_INTEGER, t) => {
+            if [UNSIGNED_BYTE, UNSIGNED_SHORT, UNSIGNED_INT].contains(&t) {
+                (RGBA_INTEGER, UNSIGNED_INT)
+            } else {
+                (RGBA_INTEGER, INT)
+            }
+        }
+        (_, FLOAT) | (_, HALF_FLOAT) | (_, UNSIGNED_INT_10F_11F_11F_REV) => (RGBA, FLOAT),
+        _ => (RGBA, UNSIGNED_BYTE),
+    };
+    let transfer = format.get_transfer();
+    if transfer == guaranteed || transfer == implementation {
+        Ok(())
+    } else {
+        Err(GlError::UnsupportedReadFormat(format))
+    }
+}
+
+// Rows are packed tightly, whatever pack alignment and pixel pack buffer the caller had is put back.
+// The caller's buffer is taken off even without fences, or a read to memory would land in it.
+fn read_into(
+    loader: &Loader,
+    rect: Rect,
+    format: InternalFormat,
+    buffer: UInt,
+    pixels: *mut c_void,
+) {
+    let pack_buffers = has_pack_binding(loader);
+    let mut alignment = 4;
+    loader.get_integer_v(PACK_ALIGNMENT, &mut alignment);
+    let mut previous = 0;
+    if pack_buffers {
+        loader.get_integer_v(PIXEL_PACK_BUFFER_BINDING, &mut previous);
+        loader.bind_buffer(BufferTarget::PixelPack.into(), buffer);
+    }
+    loader.pixel_storei(PACK_ALIGNMENT, 1);
+    let (transfer_format, transfer_type) = format.get_transfer();
+    loader.read_pixels(
+        rect.x,
+        rect.y,
+        rect.width,
+        rect.height,
+        transfer_format,
+        transfer_type,
+        pixels,
+    );
+    loader.pixel_storei(PACK_ALIGNMENT, alignment);
+    if pack_buffers {
+        loader.bind_buffer(BufferTarget::PixelPack.into(), previous as UInt);
+    }
+}
+
+pub fn read_pixels(
+    loader: &Loader,
+    rect: Rect,
+    format: InternalFormat,
+) -> Result<Vec<u8>, GlError> {
+    check_format(loader, format)?;
+    let mut data = vec![0u8; rect.get_byte_size(format)];
+    if data.is_empty() {
+        return Ok(data);
+    }
+    read_into(loader, rect, format, 0, data.as_mut_ptr() as *mut c_void);
+    Ok(data)
+}
+
+pub struct PixelReadback {
+    loader: Arc<Loader>,
+    rect: Rect,
+    format: InternalFormat,
+    buffer: Option<Buffer>,
+    fence: SyncObject,
+    // Filled right away when pixel pack buffers or fences are not supported, or the rect is empty.
+    data: Option<Vec<u8>>,
+}
+
+impl PixelReadback {
+    pub fn new(loader: &Arc<Loader>, rect: Rect, format: InternalFormat) -> Result<Self, GlError> {
+        // An empty buffer can not be mapped.
+        if !has_pixel_buffers(loader) || rect.get_byte_size(format) == 0 {
+            return Ok(Self {
+                loader: loader.clone(),
+                rect,
+                format,
+                buffer: None,
+                fence: null(),
+                data: Some(read_pixels(loader, rect, format)?),
+            });
+        }
+        check_format(loader, format)?;
+        let buffer = Buffer::new(loader);
+        let mut previous = 0;
+        loader.get_integer_v(PIXEL_PACK_BUFFER_BINDING, &mut previous);
+        buffer.bind(BufferTarget::PixelPack);
+        loader.buffer_data(
+            BufferTarget::PixelPack.into(),
+            rect.get_byte_size(format) as SizeIPtr,
+            null(),
+            BufferUsage::StreamRead.into(),
+        );
+        loader.bind_buffer(BufferTarget::PixelPack.into(), previous as UInt);
+        read_into(loader, rect, format, buffer.get_id(), null_mut());
+        let fence = loader.fence_sync(SYNC_GPU_COMMANDS_COMPLETE, 0);
+        // Without a flush the fence might never get to the GPU while nobody waits on it.
+        loader.flush();
+        Ok(Self {
+            loader: loader.clone(),
+            rect,
+            format,
+            buffer: Some(buffer),
+            fence,
+            data: None,
+        })
+    }
+
+    pub fn get_rect(&self) -> Rect {
+        self.rect
+    }
+
+    pub fn get_format(&self) -> InternalFormat {
+        self.format
+    }
+
+    // Never blocks, poll it once per frame and call wait when it turns true. A failed fence also
+    // turns it true, so wait can report it.
+    pub fn is_ready(&self) -> bool {
+        if self.data.is_some() {
+            return true;
+        }
+        let status = self.loader.client_wait_sync(self.fence, 0, 0);
+        status == ALREADY_SIGNALED || status == CONDITION_SATISFIED || status == WAIT_FAILED
+    }
+
+    pub fn wait(mut self) -> Result<Vec<u8>, GlError> {
+        if let Some(data) = self.data.take() {
+            return Ok(data);
+        }
+        let loader = &self.loader;
+        if WAIT_FAILED
+            == loader.client_wait_sync(self.fence, SYNC_FLUSH_COMMANDS_BIT, TIMEOUT_IGNORED)
+        {
+            log_e!("Waiting for the fence of a readback failed.");
+            return Err(GlError::ReadbackFailed);
+        }
+        let buffer = self.buffer.as_ref().unwrap();
+        let size = self.rect.get_byte_size(self.format);
+        let mut previous = 0;
+        loader.get_integer_v(PIXEL_PACK_BUFFER_BINDING, &mut previous);
+        buffer.bind(BufferTarget::PixelPack);
+        let mapped = loader.map_buffer_range(
+            BufferTarget::PixelPack.into(),
+            0,
+            size as SizeIPtr,
+            MAP_READ_BIT,
+        );
+        let data = if mapped.is_null() {
+            log_e!("Can not map the pixel pack buffer of a readback.");
+            Err(GlError::ReadbackFailed)
+        } else {
+            let mut data = vec![0u8; size];
+            unsafe { copy_nonoverlapping(mapped as *const u8, data.as_mut_ptr(), size) };
+            loader.unmap_buffer(BufferTarget::PixelPack.into());
+            Ok(data)
+        };
+        loader.bind_buffer(BufferTarget::PixelPack.into(), previous as UInt);
+        data
+    }
+}
+
+impl Drop for PixelReadback {
+    fn drop(&mut self) {
+        if !self.fence.is_null() {
+            self.loader.delete_sync(self.fence);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "gl-mock"))]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            constants::{PIXEL_PACK_BUFFER, RGB, UNSIGNED_SHORT_5_6_5},
+            mock,
+        },
+    };
+
+    const PIXELS: [u8; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+
+    #[allow(clippy::arc_with_non_send_sync)]
+    fn new_loader_without_fences() -> Arc<Loader> {
+        mock::reset();
+        let mut loader = Loader::new_mock();
+        loader.fence_sync = None;
+        Arc::new(loader)
+    }
+
+    #[test]
+    fn reads_around_the_callers_pack_buffer_without_fences() {
+        let loader = new_loader_without_fences();
+        let caller = Buffer::new(&loader);
+        caller.set_data(BufferTarget::PixelPack, &[0u8; 8], BufferUsage::StreamRead);
+        mock::set_integer(PACK_ALIGNMENT, 8);
+        mock::set_pixels(&PIXELS);
+        let rect = Rect::new(0, 0, 1, 2);
+        assert_eq!(
+            read_pixels(&loader, rect, InternalFormat::Rgba8),
+            Ok(PIXELS.to_vec())
+        );
+        let readback = PixelReadback::new(&loader, rect, InternalFormat::Rgba8).unwrap();
+        assert!(readback.is_ready());
+        assert_eq!(readback.wait(), Ok(PIXELS.to_vec()));
+        assert_eq!(mock::get_buffer_data(caller.get_id()), Some(vec![0; 8]));
+        assert_eq!(mock::get_bound_buffer(PIXEL_PACK_BUFFER), caller.get_id());
+        let stores = mock::get_calls_of("glPixelStorei");
+        assert_eq!(stores.last().unwrap().args, vec![PACK_ALIGNMENT as u64, 8]);
+    }
+
+    #[test]
+    fn reads_through_a_pixel_pack_buffer() {
+        let loader = mock::new_loader();
+        mock::set_pixels(&PIXELS);
+        let readback =
+            PixelReadback::new(&loader, Rect::new(0, 0, 2, 1), InternalFormat::Rgba8).unwrap();
+        let read = mock::get_calls_of("glReadPixels");
+        assert_eq!(read[0].args[6], 0);
+        assert_eq!(mock::get_bound_buffer(PIXEL_PACK_BUFFER), 0);
+        assert_eq!(mock::get_calls_of("glFenceSync").len(), 1);
+        assert!(readback.is_ready());
+        assert_eq!(readback.wait(), Ok(PIXELS.to_vec()));
+        assert_eq!(mock::get_bound_buffer(PIXEL_PACK_BUFFER), 0);
+        assert_eq!(mock::get_calls_of("glDeleteSync").len(), 1);
+    }
+
+    #[test]
+    fn empty_rects_read_nothing() {
+        let loader = mock::new_loader();
+        let rect = Rect::new(0, 0, 0, 4);
+        assert_eq!(
+            read_pixels(&loader, rect, InternalFormat::Rgba8),
+            Ok(Vec::new())
+        );
+        let readback = PixelReadback::new(&loader, rect, InternalFormat::Rgba8).unwrap();
+        assert_eq!(readback.wait(), Ok(Vec::new()));
+        assert!(mock::get_calls_of("glReadPixels").is_empty());
+        assert!(mock::get_calls_of("glMapBufferRange").is_empty());
+    }
+
+    #[test]
+    fn gles_reads_only_the_guaranteed_and_implementation_formats() {
+        let loader = mock::new_loader();
+        mock::set_version(b"OpenGL ES 3.0 Mock\0");
+        mock::set_integer(IMPLEMENTATION_COLOR_READ_FORMAT, RGB as i32);
+        mock::set_integer(IMPLEMENTATION_COLOR_READ_TYPE, UNSIGNED_SHORT_5_6_5 as i32);
+        let rect = Rect::new(0, 0, 1, 1);
+        assert!(read_pixels(&loader, rect, InternalFormat::Rgba8).is_ok());
+        assert!(read_pixels(&loader, rect, InternalFormat::Rgb565).is_ok());
+        assert_eq!(
+            read_pixels(&loader, rect, InternalFormat::R8),
+            Err(GlError::UnsupportedReadFormat(InternalFormat::R8))
+        );
+        assert_eq!(
+            PixelReadback::new(&loader, rect, InternalFormat::R8).err(),
+            Some(GlError::UnsupportedReadFormat(InternalFormat::R8))
+        );
+    }
+
+    #[test]
+    fn gles2_reads_without_the_pack_buffer_binding() {
+        let loader = mock::new_loader();
+        mock::set_version(b"OpenGL ES 2.0 Mock\0");
+        mock::set_pixels(&PIXELS[..4]);
+        let readback =
+            PixelReadback::new(&loader, Rect::new(0, 0, 1, 1), InternalFormat::Rgba8).unwrap();
+        assert_eq!(readback.wait(), Ok(PIXELS[..4].to_vec()));
+        assert!(mock::get_calls_of("glBindBuffer").is_empty());
+        assert!(mock::get_calls_of("glFenceSync").is_empty());
+    }
+}
@@ -0,0 +1,224 @@
+use {
+    super::{
+        constants::{
+            UniformType, ACTIVE_ATTRIBUTES, ACTIVE_ATTRIBUTE_MAX_LENGTH, ACTIVE_UNIFORMS,
+            ACTIVE_UNIFORM_BLOCKS, ACTIVE_UNIFORM_BLOCK_MAX_NAME_LENGTH, ACTIVE_UNIFORM_MAX_LENGTH,
+            UNIFORM_ARRAY_STRIDE, UNIFORM_BLOCK_ACTIVE_UNIFORMS,
+            UNIFORM_BLOCK_ACTIVE_UNIFORM_INDICES, UNIFORM_BLOCK_BINDING, UNIFORM_BLOCK_DATA_SIZE,
+            UNIFORM_BLOCK_INDEX, UNIFORM_MATRIX_STRIDE, UNIFORM_OFFSET,
+        },
+        error::GlError,
+        loader::Loader,
+        types::{Enumerated, SInt, SizeI, UInt},
+    },
+    std::{convert::TryFrom, ffi::CString, os::raw::c_char},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UniformInfo {
+    pub name: String,
+    pub gl_type: Enumerated,
+    pub array_size: SInt,
+    pub location: SInt,
+    pub block_index: SInt,
+    pub offset: SInt,
+    pub array_stride: SInt,
+    pub matrix_stride: SInt,
+}
+
+impl UniformInfo {
+    pub fn get_type(&self) -> Option<UniformType> {
+        UniformType::try_from(self.gl_type).ok()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttributeInfo {
+    pub name: String,
+    pub gl_type: Enumerated,
+    pub array_size: SInt,
+    pub location: SInt,
+}
+
+impl AttributeInfo {
+    pub fn get_type(&self) -> Option<UniformType> {
+        UniformType::try_from(self.gl_type).ok()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UniformBlockInfo {
+    pub name: String,
+    pub index: UInt,
+    pub binding: SInt,
+    pub data_size: SInt,
+    pub uniforms: Vec<UInt>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Reflection {
+    pub uniforms: Vec<UniformInfo>,
+    pub attributes: Vec<AttributeInfo>,
+    pub uniform_blocks: Vec<UniformBlockInfo>,
+}
+
+fn read_name(max_length: SInt, f: impl FnOnce(SizeI, *mut SizeI, *mut c_char)) -> String {
+    let mut name = vec![0u8; max_length.max(1) as usize];
+    let mut length = 0;
+    f(
+        name.len() as SizeI,
+        &mut length,
+        name.as_mut_ptr() as *mut c_char,
+    );
+    name.truncate(length as usize);
+    String::from_utf8_lossy(&name).into_owned()
+}
+
+fn to_c_name(name: &str) -> Result<CString, GlError> {
+    CString::new(name).map_err(|_| GlError::InvalidName(name.to_string()))
+}
+
+impl Reflection {
+    pub fn new(loader: &Loader, program: UInt) -> Result<Self, GlError> {
+        let get = |pname| {
+            let mut v = 0;
+            loader.get_program_iv(program, pname, &mut v);
+            v
+        };
+
+        let max_length = get(ACTIVE_UNIFORM_MAX_LENGTH);
+        let mut uniforms = (0..get(ACTIVE_UNIFORMS) as UInt)
+            .map(|i| {
+                let mut array_size = 0;
+                let mut gl_type = 0;
+                let name = read_name(max_length, |size, length, name| {
+                    loader.get_active_uniform(
+                        program,
+                        i,
+                        size,
+                        length,
+                        &mut array_size,
+                        &mut gl_type,
+                        name,
+                    )
+                });
+                let c_name = to_c_name(&name)?;
+                Ok(UniformInfo {
+                    location: loader.get_uniform_location(program, c_name.as_ptr()),
+                    name,
+                    gl_type,
+                    array_size,
+                    block_index: -1,
+                    offset: -1,
+                    array_stride: -1,
+                    matrix_stride: -1,
+                })
+            })
+            .collect::<Result<Vec<_>, GlError>>()?;
+
+        let max_length = get(ACTIVE_ATTRIBUTE_MAX_LENGTH);
+        let attributes = (0..get(ACTIVE_ATTRIBUTES) as UInt)
+            .map(|i| {
+                let mut array_size = 0;
+                let mut gl_type = 0;
+                let name = read_name(max_length, |size, length, name| {
+                    loader.get_active_attrib(
+                        program,
+                        i,
+                        size,
+                        length,
+                        &mut array_size,
+                        &mut gl_type,
+                        name,
+                    )
+                });
+                let c_name = to_c_name(&name)?;
+                Ok(AttributeInfo {
+                    location: loader.get_attrib_location(program, c_name.as_ptr()),
+                    name,
+                    gl_type,
+                    array_size,
+                })
+            })
+            .collect::<Result<_, GlError>>()?;
+
+        let mut uniform_blocks = Vec::new();
+        if loader.get_active_uniformsiv.is_some()
+            && loader.get_active_uniform_blockiv.is_some()
+            && loader.get_active_uniform_block_name.is_some()
+        {
+            let indices: Vec<UInt> = (0..uniforms.len() as UInt).collect();
+            let mut values = vec![0; indices.len()];
+            for &pname in &[
+                UNIFORM_BLOCK_INDEX,
+                UNIFORM_OFFSET,
+                UNIFORM_ARRAY_STRIDE,
+                UNIFORM_MATRIX_STRIDE,
+            ] {
+                if indices.is_empty() {
+                    break;
+                }
+                loader.get_active_uniformsiv(
+                    program,
+                    indices.len() as SizeI,
+                    indices.as_ptr(),
+                    pname,
+                    values.as_mut_ptr(),
+                );
+                for (u, &v) in uniforms.iter_mut().zip(&values) {
+                    match pname {
+                        UNIFORM_BLOCK_INDEX => u.block_index = v,
+                        UNIFORM_OFFSET => u.offset = v,
+                        UNIFORM_ARRAY_STRIDE => u.array_stride = v,
+                        _ => u.matrix_stride = v,
+                    }
+                }
+            }
+
+            let max_length = get(ACTIVE_UNIFORM_BLOCK_MAX_NAME_LENGTH);
+            for index in 0..get(ACTIVE_UNIFORM_BLOCKS) as UInt {
+                let block = |pname| {
+                    let mut v = 0;
+                    loader.get_active_uniform_blockiv(program, index, pname, &mut v);
+                    v
+                };
+                let mut members = vec![0; block(UNIFORM_BLOCK_ACTIVE_UNIFORMS).max(0) as usize];
+                if !members.is_empty() {
+                    loader.get_active_uniform_blockiv(
+                        program,
+                        index,
+                        UNIFORM_BLOCK_ACTIVE_UNIFORM_INDICES,
+                        members.as_mut_ptr(),
+                    );
+                }
+                uniform_blocks.push(UniformBlockInfo {
+                    name: read_name(max_length, |size, length, name| {
+                        loader.get_active_uniform_block_name(program, index, size, length, name)
+                    }),
+                    index,
+                    binding: block(UNIFORM_BLOCK_BINDING),
+                    data_size: block(UNIFORM_BLOCK_DATA_SIZE),
+                    uniforms: members.into_iter().map(|m| m as UInt).collect(),
+                });
+            }
+        }
+
+        Ok(Self {
+            uniforms,
+            attributes,
+            uniform_blocks,
+        })
+    }
+
+    pub fn get_uniform(&self, name: &str) -> Option<&UniformInfo> {
+        self.uniforms.iter().find(|u| u.name == name)
+    }
+
+    pub fn get_attribute(&self, name: &str) -> Option<&AttributeInfo> {
+        self.attributes.iter().find(|a| a.name == name)
+    }
+
+    pub fn get_uniform_block(&self, name: &str) -> Option<&UniformBlockInfo> {
+        self.uniform_blocks.iter().find(|b| b.name == name)
+    }
+}
@@ -0,0 +1,428 @@
+use {
+    super::{
+        error::{GlError, SwapStatus},
+        manager::Manager,
+    },
+    crate::window::log::log_e,
+    std::{
+        collections::VecDeque,
+        future::Future,
+        panic::resume_unwind,
+        pin::Pin,
+        sync::{
+            mpsc::{channel, Sender},
+            Arc, Condvar, Mutex,
+        },
+        task::{Context as TaskContext, Poll, Waker},
+        thread::{spawn, JoinHandle},
+    },
+};
+
+pub type RenderFn = Box<dyn FnOnce(&mut Manager) + Send>;
+
+pub enum Command {
+    Run(RenderFn),
+    SetSwapInterval(i32),
+    EndFrame,
+}
+
+enum Message {
+    Command(Command),
+    Stop,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Frame(u64);
+
+const FAILED_FRAMES: usize = 64;
+
+struct FrameState {
+    completed: u64,
+    // The latest failed swaps with their frames, a frame missing here swapped fine.
+    failed: VecDeque<(u64, SwapStatus)>,
+    stopped: bool,
+}
+
+impl FrameState {
+    fn get_status(&self, frame: Frame) -> Option<SwapStatus> {
+        if self.completed < frame.0 {
+            return None;
+        }
+        Some(
+            self.failed
+                .iter()
+                .find(|f| f.0 == frame.0)
+                .map_or(SwapStatus::Ok, |f| f.1),
+        )
+    }
+}
+
+type Frames = Arc<(Mutex<FrameState>, Condvar)>;
+
+// Wakes the waiters even when the render thread unwinds.
+struct StopOnDrop(Frames);
+
+impl Drop for StopOnDrop {
+    fn drop(&mut self) {
+        let (state, condvar) = &*self.0;
+        if let Ok(mut state) = state.lock() {
+            state.stopped = true;
+        }
+        condvar.notify_all();
+    }
+}
+
+struct Slot<T> {
+    value: Option<T>,
+    done: bool,
+    waker: Option<Waker>,
+}
+
+type Shared<T> = Arc<(Mutex<Slot<T>>, Condvar)>;
+
+pub struct RenderFuture<T> {
+    slot: Shared<T>,
+}
+
+impl<T> RenderFuture<T> {
+    // None means the render thread stopped before the call ran.
+    pub fn wait(self) -> Option<T> {
+        let (slot, condvar) = &*self.slot;
+        let mut slot = slot.lock().unwrap();
+        while !slot.done {
+            slot = condvar.wait(slot).unwrap();
+        }
+        slot.value.take()
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.slot.0.lock().unwrap().done
+    }
+}
+
+impl<T> Future for RenderFuture<T> {
+    type Output = Option<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut TaskContext) -> Poll<Self::Output> {
+        let mut slot = self.slot.0.lock().unwrap();
+        if slot.done {
+            return Poll::Ready(slot.value.take());
+        }
+        slot.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+struct Completer<T> {
+    slot: Shared<T>,
+}
+
+impl<T> Completer<T> {
+    fn complete(self, value: T) {
+        self.slot.0.lock().unwrap().value = Some(value);
+    }
+}
+
+// Dropping without a value, when the command is discarded, still resolves the future.
+impl<T> Drop for Completer<T> {
+    fn drop(&mut self) {
+        let (slot, condvar) = &*self.slot;
+        let waker = if let Ok(mut slot) = slot.lock() {
+            slot.done = true;
+            slot.waker.take()
+        } else {
+            None
+        };
+        condvar.notify_all();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+pub struct RenderThread {
+    sender: Sender<Message>,
+    frames: Frames,
+    submitted: u64,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl RenderThread {
+    // The manager is created on the render thread, so its context never leaves that thread.
+    pub fn new<F>(create: F) -> Result<Self, GlError>
+    where
+        F: FnOnce() -> Result<Manager, GlError> + Send + 'static,
+    {
+        let (sender, receiver) = channel();
+        let (created_sender, created) = channel();
+        let frames: Frames = Arc::new((
+            Mutex::new(FrameState {
+                completed: 0,
+                failed: VecDeque::new(),
+                stopped: false,
+            }),
+            Condvar::new(),
+        ));
+        let thread_frames = frames.clone();
+        let handle = spawn(move || {
+            let _stop = StopOnDrop(thread_frames.clone());
+            let mut manager = match create() {
+                Ok(manager) => {
+                    let _ = created_sender.send(Ok(()));
+                    manager
+                }
+                Err(e) => {
+                    let _ = created_sender.send(Err(e));
+                    return;
+                }
+            };
+            while let Ok(Message::Command(command)) = receiver.recv() {
+                match command {
+                    Command::Run(f) => f(&mut manager),
+                    Command::SetSwapInterval(interval) => {
+                        manager.set_swap_interval(interval);
+                    }
+                    Command::EndFrame => {
+                        let status = manager.swap_buffers();
+                        let (state, condvar) = &*thread_frames;
+                        let mut state = state.lock().unwrap();
+                        state.completed += 1;
+                        if status != SwapStatus::Ok {
+                            let frame = state.completed;
+                            if state.failed.len() == FAILED_FRAMES {
+                                state.failed.pop_front();
+                            }
+                            state.failed.push_back((frame, status));
+                        }
+                        condvar.notify_all();
+                    }
+                }
+            }
+            manager.release_current();
+        });
+        match created.recv() {
+            Ok(Ok(())) => Ok(Self {
+                sender,
+                frames,
+                submitted: 0,
+                handle: Some(handle),
+            }),
+            Ok(Err(e)) => {
+                let _ = handle.join();
+                Err(e)
+            }
+            // The constructor panicked, the panic goes on in the caller.
+            Err(_) => match handle.join() {
+                Err(panic) => resume_unwind(panic),
+                Ok(()) => unreachable!(),
+            },
+        }
+    }
+
+    pub fn send(&mut self, command: Command) {
+        if let Command::EndFrame = command {
+            self.submitted += 1;
+        }
+        if self.sender.send(Message::Command(command)).is_err() {
+            log_e!("Render thread is not running anymore.");
+        }
+    }
+
+    pub fn run<F: FnOnce(&mut Manager) + Send + 'static>(&mut self, f: F) {
+        self.send(Command::Run(Box::new(f)));
+    }
+
+    pub fn call<T, F>(&mut self, f: F) -> RenderFuture<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Manager) -> T + Send + 'static,
+    {
+        let slot = Arc::new((
+            Mutex::new(Slot {
+                value: None,
+                done: false,
+                waker: None,
+            }),
+            Condvar::new(),
+        ));
+        let completer = Completer { slot: slot.clone() };
+        self.run(move |manager| completer.complete(f(manager)));
+        RenderFuture { slot }
+    }
+
+    // The callback runs on the render thread right after the call.
+    pub fn call_with<T, F, C>(&mut self, f: F, callback: C)
+    where
+        F: FnOnce(&mut Manager) -> T + Send + 'static,
+        C: FnOnce(T) + Send + 'static,
+    {
+        self.run(move |manager| callback(f(manager)));
+    }
+
+    pub fn end_frame(&mut self) -> Frame {
+        self.send(Command::EndFrame);
+        Frame(self.submitted)
+    }
+
+    pub fn is_frame_done(&self, frame: Frame) -> bool {
+        let state = self.frames.0.lock().unwrap();
+        state.completed >= frame.0 || state.stopped
+    }
+
+    // None when the render thread stopped before finishing the frame.
+    pub fn wait_frame(&self, frame: Frame) -> Option<SwapStatus> {
+        let (state, condvar) = &*self.frames;
+        let mut state = state.lock().unwrap();
+        while state.completed < frame.0 && !state.stopped {
+            state = condvar.wait(state).unwrap();
+        }
+        state.get_status(frame)
+    }
+
+    // Waits until at most `frames` submitted frames are still pending.
+    pub fn throttle(&self, frames: u64) -> Option<SwapStatus> {
+        self.wait_frame(Frame(self.submitted.saturating_sub(frames)))
+    }
+
+    // False when the render thread panicked, the manager is dropped on the render thread.
+    pub fn stop(mut self) -> bool {
+        self.join()
+    }
+
+    fn join(&mut self) -> bool {
+        let _ = self.sender.send(Message::Stop);
+        match self.handle.take() {
+            Some(handle) => handle.join().is_ok(),
+            None => true,
+        }
+    }
+}
+
+impl Drop for RenderThread {
+    fn drop(&mut self) {
+        self.join();
+    }
+}
+
+#[cfg(all(
+    test,
+    feature = "gl-mock",
+    any(target_os = "android", target_os = "linux")
+))]
+mod tests {
+    use {
+        super::*,
+        std::{
+            panic::catch_unwind,
+            sync::{
+                atomic::{AtomicBool, Ordering},
+                mpsc::Receiver,
+            },
+            task::Wake,
+        },
+    };
+
+    fn new_render_thread() -> RenderThread {
+        RenderThread::new(|| Ok(Manager::new_mock())).unwrap()
+    }
+
+    // Keeps the render thread busy until the returned sender is dropped or sent to.
+    fn block(render_thread: &mut RenderThread) -> Sender<()> {
+        let (sender, receiver): (Sender<()>, Receiver<()>) = channel();
+        render_thread.run(move |_| {
+            let _ = receiver.recv();
+        });
+        sender
+    }
+
+    struct Flag(AtomicBool);
+
+    impl Wake for Flag {
+        fn wake(self: Arc<Self>) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn frames_complete_in_order() {
+        let mut render_thread = new_render_thread();
+        let first = render_thread.end_frame();
+        let second = render_thread.end_frame();
+        assert!(first < second);
+        assert_eq!(render_thread.wait_frame(second), Some(SwapStatus::Ok));
+        assert!(render_thread.is_frame_done(first));
+        assert_eq!(render_thread.wait_frame(first), Some(SwapStatus::Ok));
+    }
+
+    #[test]
+    fn throttle_waits_for_older_frames() {
+        let mut render_thread = new_render_thread();
+        let blocker = block(&mut render_thread);
+        let frames: Vec<Frame> = (0..3).map(|_| render_thread.end_frame()).collect();
+        assert!(!render_thread.is_frame_done(frames[0]));
+        drop(blocker);
+        assert_eq!(render_thread.throttle(1), Some(SwapStatus::Ok));
+        assert!(render_thread.is_frame_done(frames[1]));
+        assert_eq!(render_thread.throttle(0), Some(SwapStatus::Ok));
+        assert!(render_thread.is_frame_done(frames[2]));
+    }
+
+    #[test]
+    fn call_completes_the_future() {
+        let mut render_thread = new_render_thread();
+        let blocker = block(&mut render_thread);
+        let mut future = render_thread.call(|manager| {
+            let mut buffer = 0;
+            manager.get_loader().gen_buffers(1, &mut buffer);
+            buffer
+        });
+        assert!(!future.is_ready());
+        let flag = Arc::new(Flag(AtomicBool::new(false)));
+        let waker = Waker::from(flag.clone());
+        let mut cx = TaskContext::from_waker(&waker);
+        assert_eq!(Pin::new(&mut future).poll(&mut cx), Poll::Pending);
+        drop(blocker);
+        let (sender, receiver) = channel();
+        render_thread.call_with(
+            |manager| manager.get_size(),
+            move |size| sender.send(size).unwrap(),
+        );
+        assert_eq!(receiver.recv(), Ok((0, 0)));
+        assert!(future.is_ready());
+        assert!(flag.0.load(Ordering::SeqCst));
+        assert_eq!(Pin::new(&mut future).poll(&mut cx), Poll::Ready(Some(1)));
+        assert_eq!(render_thread.call(|_| 2).wait(), Some(2));
+    }
+
+    #[test]
+    fn stop_runs_the_queued_commands() {
+        let mut render_thread = new_render_thread();
+        let future = render_thread.call(|_| 3);
+        let frame = render_thread.end_frame();
+        let frames = render_thread.frames.clone();
+        assert!(render_thread.stop());
+        assert_eq!(future.wait(), Some(3));
+        let state = frames.0.lock().unwrap();
+        assert!(state.stopped);
+        assert_eq!(state.get_status(frame), Some(SwapStatus::Ok));
+    }
+
+    #[test]
+    fn a_panic_stops_the_render_thread() {
+        let mut render_thread = new_render_thread();
+        render_thread.run(|_| panic!("render closure panicked"));
+        assert_eq!(render_thread.call(|_| 4).wait(), None);
+        let frame = render_thread.end_frame();
+        assert_eq!(render_thread.wait_frame(frame), None);
+        assert!(render_thread.is_frame_done(frame));
+        assert!(!render_thread.stop());
+    }
+
+    #[test]
+    fn creation_errors_and_panics_reach_the_caller() {
+        let result = RenderThread::new(|| Err(GlError::DisplayNotFound));
+        assert_eq!(result.err(), Some(GlError::DisplayNotFound));
+        let result = catch_unwind(|| RenderThread::new(|| panic!("creation panicked")));
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,1089 @@
+use {
+    super::{
+        constants::{
+            self, ALPHA, BGR, BGRA, BLUE, BYTE, DEPTH_COMPONENT, DEPTH_STENCIL, DOUBLE,
+            FLOAT_32_UNSIGNED_INT_24_8_REV, GREEN, HALF_FLOAT, HALF_FLOAT_OES, INT_2_10_10_10_REV,
+            LUMINANCE, LUMINANCE_ALPHA, RED, RED_INTEGER, RG, RGB, RGB_INTEGER, RG_INTEGER, SHORT,
+            STENCIL_INDEX, UNSIGNED_BYTE, UNSIGNED_INT_10F_11F_11F_REV, UNSIGNED_INT_24_8,
+            UNSIGNED_INT_2_10_10_10_REV, UNSIGNED_INT_5_9_9_9_REV, UNSIGNED_SHORT,
+            UNSIGNED_SHORT_4_4_4_4, UNSIGNED_SHORT_5_5_5_1, UNSIGNED_SHORT_5_6_5,
+        },
+        loader::Loader,
+        types::{DebugProc, Enumerated, SInt, UInt},
+    },
+    crate::window::log::log_e,
+    std::{
+        collections::HashMap,
+        ffi::CStr,
+        fs::File,
+        io::{self, BufReader, BufWriter, Read, Write},
+        mem::take,
+        os::raw::c_char,
+        path::Path,
+        ptr::null_mut,
+        slice::from_raw_parts,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Mutex,
+        },
+    },
+};
+
+const MAGIC: &[u8; 8] = b"GLTRACE1";
+// Enough for every query that writes a fixed number of values, larger outputs are sized from the call.
+const SCRATCH_SIZE: usize = 1 << 16;
+const PAYLOAD_DATA: u8 = 0;
+const PAYLOAD_OFFSET: u8 = 1;
+
+// Trace arguments are widened to u64, so the values compared against them are too.
+const ARRAY_BUFFER: u64 = constants::ARRAY_BUFFER as u64;
+const ELEMENT_ARRAY_BUFFER: u64 = constants::ELEMENT_ARRAY_BUFFER as u64;
+const PIXEL_PACK_BUFFER: u64 = constants::PIXEL_PACK_BUFFER as u64;
+const PIXEL_UNPACK_BUFFER: u64 = constants::PIXEL_UNPACK_BUFFER as u64;
+const PACK_ALIGNMENT: u64 = constants::PACK_ALIGNMENT as u64;
+const UNPACK_ALIGNMENT: u64 = constants::UNPACK_ALIGNMENT as u64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Namespace {
+    Buffer,
+    Texture,
+    Framebuffer,
+    Renderbuffer,
+    VertexArray,
+    Query,
+    Sampler,
+    TransformFeedback,
+    Program,
+}
+
+const GENERATORS: [(&str, &str, Namespace); 8] = [
+    ("glGenBuffers", "glDeleteBuffers", Namespace::Buffer),
+    ("glGenTextures", "glDeleteTextures", Namespace::Texture),
+    (
+        "glGenFramebuffers",
+        "glDeleteFramebuffers",
+        Namespace::Framebuffer,
+    ),
+    (
+        "glGenRenderbuffers",
+        "glDeleteRenderbuffers",
+        Namespace::Renderbuffer,
+    ),
+    (
+        "glGenVertexArrays",
+        "glDeleteVertexArrays",
+        Namespace::VertexArray,
+    ),
+    ("glGenQueries", "glDeleteQueries", Namespace::Query),
+    ("glGenSamplers", "glDeleteSamplers", Namespace::Sampler),
+    (
+        "glGenTransformFeedbacks",
+        "glDeleteTransformFeedbacks",
+        Namespace::TransformFeedback,
+    ),
+];
+
+// Arguments that carry object names, they get translated to the names the replaying driver returned.
+const NAMES: [(&str, usize, Namespace); 41] = [
+    ("glBindBuffer", 1, Namespace::Buffer),
+    ("glBindBufferBase", 2, Namespace::Buffer),
+    ("glBindBufferRange", 2, Namespace::Buffer),
+    ("glBindTexture", 1, Namespace::Texture),
+    ("glFramebufferTexture", 2, Namespace::Texture),
+    ("glFramebufferTexture2D", 3, Namespace::Texture),
+    ("glFramebufferTextureLayer", 2, Namespace::Texture),
+    ("glBindFramebuffer", 1, Namespace::Framebuffer),
+    ("glBindRenderbuffer", 1, Namespace::Renderbuffer),
+    ("glFramebufferRenderbuffer", 3, Namespace::Renderbuffer),
+    ("glBindVertexArray", 0, Namespace::VertexArray),
+    ("glBeginQuery", 1, Namespace::Query),
+    ("glBindSampler", 1, Namespace::Sampler),
+    ("glSamplerParameteri", 0, Namespace::Sampler),
+    ("glSamplerParameterf", 0, Namespace::Sampler),
+    ("glBindTransformFeedback", 1, Namespace::TransformFeedback),
+    ("glAttachShader", 0, Namespace::Program),
+    ("glAttachShader", 1, Namespace::Program),
+    ("glDetachShader", 0, Namespace::Program),
+    ("glDetachShader", 1, Namespace::Program),
+    ("glShaderSource", 0, Namespace::Program),
+    ("glCompileShader", 0, Namespace::Program),
+    ("glDeleteShader", 0, Namespace::Program),
+    ("glGetShaderiv", 0, Namespace::Program),
+    ("glGetShaderInfoLog", 0, Namespace::Program),
+    ("glLinkProgram", 0, Namespace::Program),
+    ("glValidateProgram", 0, Namespace::Program),
+    ("glUseProgram", 0, Namespace::Program),
+    ("glDeleteProgram", 0, Namespace::Program),
+    ("glGetProgramiv", 0, Namespace::Program),
+    ("glGetProgramInfoLog", 0, Namespace::Program),
+    ("glProgramParameteri", 0, Namespace::Program),
+    ("glGetUniformLocation", 0, Namespace::Program),
+    ("glGetAttribLocation", 0, Namespace::Program),
+    ("glBindAttribLocation", 0, Namespace::Program),
+    ("glBindFragDataLocation", 0, Namespace::Program),
+    ("glGetActiveUniform", 0, Namespace::Program),
+    ("glGetActiveAttrib", 0, Namespace::Program),
+    ("glGetActiveUniformsiv", 0, Namespace::Program),
+    ("glGetUniformBlockIndex", 0, Namespace::Program),
+    ("glUniformBlockBinding", 0, Namespace::Program),
+];
+
+// Pointer arguments that are offsets into the bound array buffer, or client memory when none is bound.
+const ATTRIB_POINTERS: [(&str, usize); 2] =
+    [("glVertexAttribPointer", 5), ("glVertexAttribIPointer", 4)];
+
+// Index pointers with the index of their count and type, they are offsets when an element buffer is bound.
+const INDICES: [(&str, usize, usize, usize); 4] = [
+    ("glDrawElements", 3, 1, 2),
+    ("glDrawElementsInstanced", 3, 1, 2),
+    ("glDrawRangeElements", 5, 3, 4),
+    ("glDrawElementsBaseVertex", 3, 1, 2),
+];
+
+// Name strings passed to program queries.
+const STRINGS: [(&str, usize); 5] = [
+    ("glGetUniformLocation", 1),
+    ("glGetAttribLocation", 1),
+    ("glGetUniformBlockIndex", 1),
+    ("glBindAttribLocation", 2),
+    ("glBindFragDataLocation", 2),
+];
+
+pub(crate) trait TraceValue {
+    fn to_trace(&self) -> u64;
+    fn from_trace(args: &mut ReplayArgs) -> Self;
+}
+
+macro_rules! trace_integer {
+    ($($t:ty),*) => {
+        $(impl TraceValue for $t {
+            fn to_trace(&self) -> u64 {
+                *self as u64
+            }
+
+            fn from_trace(args: &mut ReplayArgs) -> Self {
+                args.next() as $t
+            }
+        })*
+    };
+}
+
+trace_integer!(u8, i8, u16, i16, u32, i32, u64, i64, isize, usize);
+
+impl TraceValue for f32 {
+    fn to_trace(&self) -> u64 {
+        self.to_bits() as u64
+    }
+
+    fn from_trace(args: &mut ReplayArgs) -> Self {
+        f32::from_bits(args.next() as u32)
+    }
+}
+
+impl TraceValue for f64 {
+    fn to_trace(&self) -> u64 {
+        self.to_bits()
+    }
+
+    fn from_trace(args: &mut ReplayArgs) -> Self {
+        f64::from_bits(args.next())
+    }
+}
+
+impl<T> TraceValue for *const T {
+    fn to_trace(&self) -> u64 {
+        *self as usize as u64
+    }
+
+    fn from_trace(args: &mut ReplayArgs) -> Self {
+        args.pointer() as *const T
+    }
+}
+
+impl<T> TraceValue for *mut T {
+    fn to_trace(&self) -> u64 {
+        *self as usize as u64
+    }
+
+    fn from_trace(args: &mut ReplayArgs) -> Self {
+        args.pointer() as *mut T
+    }
+}
+
+// Callbacks point into the traced process, they can not be replayed.
+impl TraceValue for DebugProc {
+    fn to_trace(&self) -> u64 {
+        self.map_or(0, |f| f as usize as u64)
+    }
+
+    fn from_trace(args: &mut ReplayArgs) -> Self {
+        args.next();
+        None
+    }
+}
+
+impl TraceValue for () {
+    fn to_trace(&self) -> u64 {
+        0
+    }
+
+    fn from_trace(_: &mut ReplayArgs) -> Self {}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Payload {
+    pub index: u8,
+    pub offset: bool,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceCall {
+    pub name: String,
+    pub args: Vec<u64>,
+    pub result: u64,
+    pub payloads: Vec<Payload>,
+}
+
+impl TraceCall {
+    fn get_payload(&self, index: usize) -> Option<&Payload> {
+        self.payloads.iter().find(|p| p.index as usize == index)
+    }
+
+    fn write(&self, w: &mut dyn Write) -> io::Result<()> {
+        w.write_all(&(self.name.len() as u16).to_le_bytes())?;
+        w.write_all(self.name.as_bytes())?;
+        w.write_all(&[self.args.len() as u8])?;
+        for a in &self.args {
+            w.write_all(&a.to_le_bytes())?;
+        }
+        w.write_all(&self.result.to_le_bytes())?;
+        w.write_all(&[self.payloads.len() as u8])?;
+        for p in &self.payloads {
+            w.write_all(&[
+                p.index,
+                if p.offset {
+                    PAYLOAD_OFFSET
+                } else {
+                    PAYLOAD_DATA
+                },
+            ])?;
+            w.write_all(&(p.data.len() as u32).to_le_bytes())?;
+            w.write_all(&p.data)?;
+        }
+        Ok(())
+    }
+
+    fn read(r: &mut dyn Read) -> io::Result<Option<Self>> {
+        let mut u8s = [0u8; 1];
+        let mut u16s = [0u8; 2];
+        let mut u32s = [0u8; 4];
+        let mut u64s = [0u8; 8];
+        match r.read_exact(&mut u16s) {
+            Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            r => r?,
+        }
+        let mut name = vec![0u8; u16::from_le_bytes(u16s) as usize];
+        r.read_exact(&mut name)?;
+        let name =
+            String::from_utf8(name).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        r.read_exact(&mut u8s)?;
+        let mut args = Vec::with_capacity(u8s[0] as usize);
+        for _ in 0..u8s[0] {
+            r.read_exact(&mut u64s)?;
+            args.push(u64::from_le_bytes(u64s));
+        }
+        r.read_exact(&mut u64s)?;
+        let result = u64::from_le_bytes(u64s);
+        r.read_exact(&mut u8s)?;
+        let mut payloads = Vec::with_capacity(u8s[0] as usize);
+        for _ in 0..u8s[0] {
+            let mut header = [0u8; 2];
+            r.read_exact(&mut header)?;
+            r.read_exact(&mut u32s)?;
+            let mut data = vec![0u8; u32::from_le_bytes(u32s) as usize];
+            r.read_exact(&mut data)?;
+            payloads.push(Payload {
+                index: header[0],
+                offset: header[1] == PAYLOAD_OFFSET,
+                data,
+            });
+        }
+        Ok(Some(Self {
+            name,
+            args,
+            result,
+            payloads,
+        }))
+    }
+}
+
+#[derive(Default)]
+struct ClientArray {
+    name: String,
+    args: Vec<u64>,
+    enabled: bool,
+}
+
+struct Recorder {
+    writer: Box<dyn Write + Send>,
+    pack_buffer: bool,
+    unpack_buffer: bool,
+    unpack_alignment: u64,
+    array_buffer: u64,
+    // The element array buffer binding belongs to the vertex array object.
+    element_buffers: HashMap<u64, u64>,
+    vertex_array: u64,
+    client_arrays: HashMap<u64, ClientArray>,
+}
+
+static TRACING: AtomicBool = AtomicBool::new(false);
+static RECORDER: Mutex<Option<Recorder>> = Mutex::new(None);
+
+pub fn start(path: &Path) -> io::Result<()> {
+    start_with(Box::new(BufWriter::new(File::create(path)?)))
+}
+
+pub fn start_with(mut writer: Box<dyn Write + Send>) -> io::Result<()> {
+    writer.write_all(MAGIC)?;
+    let mut recorder = RECORDER.lock().unwrap();
+    if let Some(mut r) = recorder.take() {
+        r.writer.flush()?;
+    }
+    *recorder = Some(Recorder {
+        writer,
+        pack_buffer: false,
+        unpack_buffer: false,
+        unpack_alignment: 4,
+        array_buffer: 0,
+        element_buffers: HashMap::new(),
+        vertex_array: 0,
+        client_arrays: HashMap::new(),
+    });
+    TRACING.store(true, Ordering::Release);
+    Ok(())
+}
+
+pub fn stop() -> io::Result<()> {
+    TRACING.store(false, Ordering::Release);
+    if let Some(mut r) = RECORDER.lock().unwrap().take() {
+        r.writer.flush()?;
+    }
+    Ok(())
+}
+
+pub fn is_tracing() -> bool {
+    TRACING.load(Ordering::Acquire)
+}
+
+fn get_pixels_size(width: u64, height: u64, format: u64, type_: u64, alignment: u64) -> usize {
+    let components = match format as Enumerated {
+        RED | ALPHA | LUMINANCE | DEPTH_COMPONENT | STENCIL_INDEX | RED_INTEGER | GREEN | BLUE => 1,
+        RG | LUMINANCE_ALPHA | RG_INTEGER | DEPTH_STENCIL => 2,
+        RGB | RGB_INTEGER | BGR => 3,
+        _ => 4,
+    };
+    let pixel = match type_ as Enumerated {
+        BYTE | UNSIGNED_BYTE => components,
+        SHORT | UNSIGNED_SHORT | HALF_FLOAT | HALF_FLOAT_OES => components * 2,
+        UNSIGNED_SHORT_5_6_5 | UNSIGNED_SHORT_4_4_4_4 | UNSIGNED_SHORT_5_5_5_1 => 2,
+        UNSIGNED_INT_2_10_10_10_REV
+        | UNSIGNED_INT_10F_11F_11F_REV
+        | UNSIGNED_INT_5_9_9_9_REV
+        | UNSIGNED_INT_24_8 => 4,
+        FLOAT_32_UNSIGNED_INT_24_8_REV => 8,
+        _ => components * 4,
+    };
+    let alignment = alignment.max(1);
+    let row = (width * pixel).div_ceil(alignment) * alignment;
+    (row * height) as usize
+}
+
+fn get_uniform_size(name: &str) -> Option<(usize, usize)> {
+    let (components, value) = match name {
+        "glUniform1fv" | "glUniform1iv" | "glUniform1uiv" => (1, 2),
+        "glUniform2fv" | "glUniform2iv" | "glUniform2uiv" => (2, 2),
+        "glUniform3fv" | "glUniform3iv" | "glUniform3uiv" => (3, 2),
+        "glUniform4fv" | "glUniform4iv" | "glUniform4uiv" => (4, 2),
+        "glUniformMatrix2fv" => (4, 3),
+        "glUniformMatrix3fv" => (9, 3),
+        "glUniformMatrix4fv" => (16, 3),
+        "glUniformMatrix2x3fv" | "glUniformMatrix3x2fv" => (6, 3),
+        "glUniformMatrix2x4fv" | "glUniformMatrix4x2fv" => (8, 3),
+        "glUniformMatrix3x4fv" | "glUniformMatrix4x3fv" => (12, 3),
+        _ => return None,
+    };
+    Some((components * 4, value))
+}
+
+// Counts and sizes are signed in GL, a negative one reads nothing.
+fn get_count(v: u64) -> u64 {
+    (v as i32).max(0) as u64
+}
+
+fn get_index_size(type_: u64) -> usize {
+    match type_ as Enumerated {
+        UNSIGNED_BYTE => 1,
+        UNSIGNED_SHORT => 2,
+        _ => 4,
+    }
+}
+
+fn get_attrib_size(size: u64, type_: u64) -> u64 {
+    // GL_BGRA stands for four components.
+    let components = if size == BGRA as u64 { 4 } else { size };
+    match type_ as Enumerated {
+        UNSIGNED_INT_2_10_10_10_REV | INT_2_10_10_10_REV | UNSIGNED_INT_10F_11F_11F_REV => 4,
+        BYTE | UNSIGNED_BYTE => components,
+        SHORT | UNSIGNED_SHORT | HALF_FLOAT | HALF_FLOAT_OES => components * 2,
+        DOUBLE => components * 8,
+        _ => components * 4,
+    }
+}
+
+// Bytes written through the output pointer of calls whose output is not bounded by a small constant.
+fn get_output_size(name: &str, args: &[u64], pack_alignment: u64) -> usize {
+    let arg = |i: usize| get_count(args.get(i).cloned().unwrap_or(0));
+    match name {
+        "glReadPixels" => get_pixels_size(arg(2), arg(3), args[4], args[5], pack_alignment),
+        "glGetBufferSubData" => arg(2) as usize,
+        "glGetShaderInfoLog" | "glGetProgramInfoLog" | "glGetShaderSource" => arg(1) as usize,
+        "glGetActiveUniform" | "glGetActiveAttrib" | "glGetActiveUniformBlockName" => {
+            arg(2) as usize
+        }
+        "glGetActiveUniformsiv" => arg(1) as usize * 4,
+        _ => 0,
+    }
+}
+
+fn read_memory(pointer: u64, size: usize) -> Vec<u8> {
+    if pointer == 0 || size == 0 {
+        return Vec::new();
+    }
+    unsafe { from_raw_parts(pointer as usize as *const u8, size) }.to_vec()
+}
+
+fn read_string(pointer: u64) -> Vec<u8> {
+    if pointer == 0 {
+        return Vec::new();
+    }
+    unsafe { CStr::from_ptr(pointer as usize as *const c_char) }
+        .to_bytes_with_nul()
+        .to_vec()
+}
+
+fn read_names(pointer: u64, count: u64) -> Vec<u64> {
+    read_memory(pointer, 4 * get_count(count) as usize)
+        .chunks(4)
+        .map(|c| UInt::from_le_bytes([c[0], c[1], c[2], c[3]]) as u64)
+        .collect()
+}
+
+impl Recorder {
+    fn get_payloads(&mut self, name: &str, args: &[u64]) -> Vec<Payload> {
+        let mut payloads = Vec::new();
+        let mut data = |index: usize, data: Vec<u8>| {
+            payloads.push(Payload {
+                index: index as u8,
+                offset: false,
+                data,
+            })
+        };
+        let element_buffer = self.get_element_buffer();
+        match name {
+            "glBindBuffer" if args[0] == PIXEL_PACK_BUFFER => self.pack_buffer = args[1] != 0,
+            "glBindBuffer" if args[0] == PIXEL_UNPACK_BUFFER => self.unpack_buffer = args[1] != 0,
+            "glBindBuffer" if args[0] == ARRAY_BUFFER => self.array_buffer = args[1],
+            "glBindBuffer" if args[0] == ELEMENT_ARRAY_BUFFER => {
+                self.element_buffers.insert(self.vertex_array, args[1]);
+            }
+            "glBindVertexArray" => self.vertex_array = args[0],
+            "glPixelStorei" if args[0] == UNPACK_ALIGNMENT => self.unpack_alignment = args[1],
+            "glEnableVertexAttribArray" | "glDisableVertexAttribArray"
+                if self.vertex_array == 0 =>
+            {
+                self.client_arrays.entry(args[0]).or_default().enabled =
+                    name == "glEnableVertexAttribArray";
+            }
+            "glGetActiveUniformsiv" => {
+                data(2, read_memory(args[2], 4 * get_count(args[1]) as usize))
+            }
+            "glBufferData" => data(2, read_memory(args[2], args[1] as usize)),
+            "glBufferSubData" => data(3, read_memory(args[3], args[2] as usize)),
+            "glTexImage2D" | "glTexSubImage2D" if !self.unpack_buffer => {
+                let (width, height) = if name == "glTexImage2D" {
+                    (args[3], args[4])
+                } else {
+                    (args[4], args[5])
+                };
+                let size = get_pixels_size(width, height, args[6], args[7], self.unpack_alignment);
+                data(8, read_memory(args[8], size));
+            }
+            "glShaderSource" => {
+                let strings = args[2] as usize as *const *const c_char;
+                let lengths = args[3] as usize as *const i32;
+                let mut source = Vec::new();
+                for i in 0..args[1] as usize {
+                    let s = unsafe { *strings.add(i) } as u64;
+                    let length = if lengths.is_null() || unsafe { *lengths.add(i) } < 0 {
+                        unsafe { CStr::from_ptr(s as usize as *const c_char) }
+                            .to_bytes()
+                            .len()
+                    } else {
+                        unsafe { *lengths.add(i) as usize }
+                    };
+                    source.extend(read_memory(s, length));
+                }
+                source.push(0);
+                data(2, source);
+            }
+            _ => {
+                if let Some((size, index)) = get_uniform_size(name) {
+                    data(index, read_memory(args[index], size * args[1] as usize));
+                } else if name == "glDrawBuffers"
+                    || GENERATORS.iter().any(|g| g.0 == name || g.1 == name)
+                {
+                    data(1, read_memory(args[1], 4 * get_count(args[0]) as usize));
+                } else if let Some(&(_, index)) = STRINGS.iter().find(|s| s.0 == name) {
+                    data(index, read_string(args[index]));
+                } else if let Some(&(_, index, count, type_)) = INDICES.iter().find(|i| i.0 == name)
+                {
+                    if element_buffer == 0 {
+                        let size = get_count(args[count]) as usize * get_index_size(args[type_]);
+                        data(index, read_memory(args[index], size));
+                    }
+                }
+            }
+        }
+        if name == "glDeleteBuffers" {
+            for buffer in read_names(args[1], args[0]) {
+                if self.array_buffer == buffer {
+                    self.array_buffer = 0;
+                }
+                self.element_buffers.retain(|_, b| *b != buffer);
+            }
+        }
+        if let Some(&(_, index)) = ATTRIB_POINTERS.iter().find(|a| a.0 == name) {
+            // Client memory is only read at draw time, it gets recorded right before each draw.
+            if self.array_buffer == 0 && self.vertex_array == 0 {
+                let array = self.client_arrays.entry(args[0]).or_default();
+                array.name = name.to_string();
+                array.args = args.to_vec();
+            } else {
+                if let Some(array) = self.client_arrays.get_mut(&args[0]) {
+                    array.args.clear();
+                }
+                payloads.push(Payload {
+                    index: index as u8,
+                    offset: true,
+                    data: Vec::new(),
+                });
+            }
+        }
+        let offset = match name {
+            "glTexImage2D" | "glTexSubImage2D" if self.unpack_buffer => Some(8),
+            "glReadPixels" if self.pack_buffer => Some(6),
+            _ => INDICES
+                .iter()
+                .find(|i| i.0 == name && element_buffer != 0)
+                .map(|i| i.1),
+        };
+        if let Some(index) = offset {
+            payloads.push(Payload {
+                index: index as u8,
+                offset: true,
+                data: Vec::new(),
+            });
+        }
+        payloads
+    }
+
+    fn get_element_buffer(&self) -> u64 {
+        self.element_buffers
+            .get(&self.vertex_array)
+            .cloned()
+            .unwrap_or(0)
+    }
+
+    // How many vertices a draw reads, only needed while client arrays are enabled.
+    fn get_vertex_count(&self, name: &str, args: &[u64]) -> Option<u64> {
+        match name {
+            "glDrawArrays" | "glDrawArraysInstanced" => {
+                Some(get_count(args[1]) + get_count(args[2]))
+            }
+            "glDrawRangeElements" => Some(get_count(args[2]) + 1),
+            _ => {
+                let &(_, index, count, type_) = INDICES.iter().find(|i| i.0 == name)?;
+                if self.get_element_buffer() != 0 {
+                    log_e!(
+                        "Client arrays of {} can not be traced with an element buffer.",
+                        name
+                    );
+                    return None;
+                }
+                let size = get_index_size(args[type_]);
+                let indices = read_memory(args[index], get_count(args[count]) as usize * size);
+                let last = indices
+                    .chunks(size)
+                    .map(|c| c.iter().rev().fold(0u64, |v, b| (v << 8) | *b as u64))
+                    .max()?;
+                let base = if name == "glDrawElementsBaseVertex" {
+                    args[4] as i32 as i64
+                } else {
+                    0
+                };
+                Some((last as i64 + base + 1).max(0) as u64)
+            }
+        }
+    }
+
+    // The client arrays a draw reads, as attribute pointer calls carrying the data.
+    fn get_client_arrays(&self, name: &str, args: &[u64]) -> Vec<TraceCall> {
+        if self.vertex_array != 0 || !self.client_arrays.values().any(|a| a.enabled) {
+            return Vec::new();
+        }
+        let vertices = match self.get_vertex_count(name, args) {
+            Some(v) if v > 0 => v,
+            _ => return Vec::new(),
+        };
+        self.client_arrays
+            .values()
+            .filter(|a| a.enabled && !a.args.is_empty())
+            .map(|a| {
+                let (index, stride) = ATTRIB_POINTERS
+                    .iter()
+                    .find(|p| p.0 == a.name)
+                    .map(|p| (p.1, a.args[p.1 - 1]))
+                    .unwrap();
+                let size = get_attrib_size(a.args[1], a.args[2]);
+                let stride = if stride == 0 { size } else { get_count(stride) };
+                let length = (vertices - 1) * stride + size;
+                TraceCall {
+                    name: a.name.clone(),
+                    args: a.args.clone(),
+                    result: 0,
+                    payloads: vec![Payload {
+                        index: index as u8,
+                        offset: false,
+                        data: read_memory(a.args[index], length as usize),
+                    }],
+                }
+            })
+            .collect()
+    }
+}
+
+pub(crate) fn record(name: &str, args: &[u64], result: u64) {
+    if !is_tracing() {
+        return;
+    }
+    let mut recorder = RECORDER.lock().unwrap();
+    let recorder = if let Some(r) = recorder.as_mut() {
+        r
+    } else {
+        return;
+    };
+    let mut calls = recorder.get_client_arrays(name, args);
+    calls.push(TraceCall {
+        name: name.to_string(),
+        payloads: recorder.get_payloads(name, args),
+        args: args.to_vec(),
+        result,
+    });
+    for call in &calls {
+        if let Err(e) = call.write(&mut recorder.writer) {
+            log_e!("Can not write the OpenGL trace: {}", e);
+            return;
+        }
+    }
+}
+
+pub(crate) struct ReplayArgs<'a> {
+    call: &'a TraceCall,
+    data: Vec<Option<*mut u8>>,
+    scratch: *mut u8,
+    index: usize,
+}
+
+impl<'a> ReplayArgs<'a> {
+    fn next(&mut self) -> u64 {
+        let v = self.call.args.get(self.index).cloned().unwrap_or(0);
+        self.index += 1;
+        v
+    }
+
+    fn pointer(&mut self) -> *mut u8 {
+        let index = self.index;
+        let v = self.next();
+        if let Some(Some(p)) = self.data.get(index) {
+            return *p;
+        }
+        match self.call.get_payload(index) {
+            Some(p) if p.offset => v as usize as *mut u8,
+            _ if v == 0 => null_mut(),
+            _ => self.scratch,
+        }
+    }
+}
+
+pub struct Replayer<R: Read> {
+    reader: R,
+    names: HashMap<(Namespace, UInt), UInt>,
+    // Uniform locations the replaying driver returned, by recorded program and location.
+    locations: HashMap<(UInt, SInt), SInt>,
+    program: UInt,
+    // Client arrays have to stay alive until the draw that reads them.
+    client_arrays: HashMap<u64, Vec<u8>>,
+    pack_alignment: u64,
+    scratch: Vec<u8>,
+}
+
+impl Replayer<BufReader<File>> {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> Replayer<R> {
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not an OpenGL trace",
+            ));
+        }
+        Ok(Self {
+            reader,
+            names: HashMap::new(),
+            locations: HashMap::new(),
+            program: 0,
+            client_arrays: HashMap::new(),
+            pack_alignment: 4,
+            scratch: vec![0; SCRATCH_SIZE],
+        })
+    }
+
+    pub fn next_call(&mut self) -> io::Result<Option<TraceCall>> {
+        TraceCall::read(&mut self.reader)
+    }
+
+    fn translate(&self, namespace: Namespace, name: u64) -> u64 {
+        self.names
+            .get(&(namespace, name as UInt))
+            .map_or(name, |n| *n as u64)
+    }
+
+    fn get_location_index(&self, recorded: &TraceCall) -> Option<(UInt, usize)> {
+        match recorded.name.as_str() {
+            "glGetUniformfv" | "glGetUniformiv" | "glGetUniformuiv" => {
+                Some((recorded.args[0] as UInt, 1))
+            }
+            "glUniformBlockBinding" | "glUniformSubroutinesuiv" => None,
+            n if n.starts_with("glProgramUniform") => Some((recorded.args[0] as UInt, 1)),
+            n if n.starts_with("glUniform") => Some((self.program, 0)),
+            _ => None,
+        }
+    }
+
+    pub fn replay_call(&mut self, loader: &Loader, recorded: &TraceCall) -> bool {
+        let mut call = recorded.clone();
+        let name = call.name.clone();
+        for &(_, index, namespace) in NAMES.iter().filter(|n| n.0 == name) {
+            if let Some(a) = call.args.get(index).cloned() {
+                call.args[index] = self.translate(namespace, a);
+            }
+        }
+        if let Some((program, index)) = self.get_location_index(recorded) {
+            if let Some(a) = call.args.get(index).cloned() {
+                if let Some(l) = self.locations.get(&(program, a as SInt)) {
+                    call.args[index] = *l as u64;
+                }
+            }
+        }
+        match name.as_str() {
+            "glUseProgram" => self.program = recorded.args[0] as UInt,
+            "glPixelStorei" if call.args[0] == PACK_ALIGNMENT => self.pack_alignment = call.args[1],
+            _ => (),
+        }
+        let output = get_output_size(&name, &call.args, self.pack_alignment);
+        if self.scratch.len() < output {
+            self.scratch.resize(output, 0);
+        }
+        let mut buffers: Vec<(usize, Vec<u8>)> = call
+            .payloads
+            .iter()
+            .filter(|p| !p.offset)
+            .map(|p| (p.index as usize, p.data.clone()))
+            .collect();
+        let generator = GENERATORS
+            .iter()
+            .find(|g| g.0 == call.name || g.1 == call.name)
+            .cloned();
+        let recorded_names: Vec<UInt> = buffers
+            .iter()
+            .filter(|b| generator.is_some() && b.0 == 1)
+            .flat_map(|b| b.1.chunks(4))
+            .map(|c| UInt::from_le_bytes([c[0], c[1], c[2], c[3]]))
+            .collect();
+        if let Some((_, delete, namespace)) = generator {
+            if delete == call.name {
+                for b in buffers.iter_mut().filter(|b| b.0 == 1) {
+                    b.1 = recorded_names
+                        .iter()
+                        .flat_map(|n| (self.translate(namespace, *n as u64) as UInt).to_le_bytes())
+                        .collect();
+                }
+            }
+        }
+        let mut data = vec![None; call.args.len()];
+        for b in &mut buffers {
+            if b.0 < data.len() {
+                data[b.0] = Some(b.1.as_mut_ptr());
+            }
+        }
+        if let Some(&(_, index)) = ATTRIB_POINTERS.iter().find(|a| a.0 == name) {
+            if let Some(b) = buffers.iter_mut().find(|b| b.0 == index) {
+                let array = self.client_arrays.entry(call.args[0]).or_default();
+                *array = take(&mut b.1);
+                data[index] = Some(array.as_mut_ptr());
+            }
+        }
+        let mut sources = Vec::new();
+        if call.name == "glShaderSource" {
+            call.args[1] = 1;
+            call.args[3] = 0;
+            if let Some(source) = data[2] {
+                sources.push(source);
+                data[2] = Some(sources.as_mut_ptr() as *mut u8);
+            }
+        }
+        let mut args = ReplayArgs {
+            call: &call,
+            data,
+            scratch: self.scratch.as_mut_ptr(),
+            index: 0,
+        };
+        let result = if let Some(r) = loader.replay_call(&call.name, &mut args) {
+            r
+        } else {
+            log_e!("OpenGL function '{}' can not be replayed.", call.name);
+            return false;
+        };
+        if let Some((generate, _, namespace)) = generator {
+            if generate == call.name {
+                let generated = buffers
+                    .iter()
+                    .filter(|b| b.0 == 1)
+                    .flat_map(|b| b.1.chunks(4))
+                    .map(|c| UInt::from_le_bytes([c[0], c[1], c[2], c[3]]));
+                for (old, new) in recorded_names.iter().zip(generated) {
+                    self.names.insert((namespace, *old), new);
+                }
+            }
+        }
+        if call.name == "glCreateShader" || call.name == "glCreateProgram" {
+            self.names
+                .insert((Namespace::Program, call.result as UInt), result as UInt);
+        }
+        if call.name == "glGetUniformLocation" {
+            self.locations.insert(
+                (recorded.args[0] as UInt, recorded.result as SInt),
+                result as SInt,
+            );
+        }
+        true
+    }
+
+    pub fn replay(&mut self, loader: &Loader) -> io::Result<usize> {
+        let mut count = 0;
+        while let Some(call) = self.next_call()? {
+            if self.replay_call(loader, &call) {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: u64 = constants::RED as u64;
+    const RGB: u64 = constants::RGB as u64;
+    const RGBA: u64 = constants::RGBA as u64;
+    const UNSIGNED_BYTE: u64 = constants::UNSIGNED_BYTE as u64;
+    const UNSIGNED_SHORT: u64 = constants::UNSIGNED_SHORT as u64;
+    const UNSIGNED_SHORT_5_6_5: u64 = constants::UNSIGNED_SHORT_5_6_5 as u64;
+    const HALF_FLOAT: u64 = constants::HALF_FLOAT as u64;
+    const FLOAT: u64 = constants::FLOAT as u64;
+    const STATIC_DRAW: u64 = constants::STATIC_DRAW as u64;
+    const TRIANGLES: u64 = constants::TRIANGLES as u64;
+
+    fn new_recorder() -> Recorder {
+        Recorder {
+            writer: Box::new(io::sink()),
+            pack_buffer: false,
+            unpack_buffer: false,
+            unpack_alignment: 4,
+            array_buffer: 0,
+            element_buffers: HashMap::new(),
+            vertex_array: 0,
+            client_arrays: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn call_round_trip() {
+        let calls = vec![
+            TraceCall {
+                name: "glBufferData".to_string(),
+                args: vec![ARRAY_BUFFER, 3, 0xDEAD_BEEF, STATIC_DRAW],
+                result: 0,
+                payloads: vec![Payload {
+                    index: 2,
+                    offset: false,
+                    data: vec![1, 2, 3],
+                }],
+            },
+            TraceCall {
+                name: "glDrawElements".to_string(),
+                args: vec![TRIANGLES, 6, UNSIGNED_SHORT, 12],
+                result: 0,
+                payloads: vec![Payload {
+                    index: 3,
+                    offset: true,
+                    data: Vec::new(),
+                }],
+            },
+            TraceCall {
+                name: "glCreateProgram".to_string(),
+                args: Vec::new(),
+                result: 7,
+                payloads: Vec::new(),
+            },
+        ];
+        let mut bytes = Vec::new();
+        for c in &calls {
+            c.write(&mut bytes).unwrap();
+        }
+        let mut reader = &bytes[..];
+        for c in &calls {
+            assert_eq!(TraceCall::read(&mut reader).unwrap().as_ref(), Some(c));
+        }
+        assert_eq!(TraceCall::read(&mut reader).unwrap(), None);
+        let mut truncated = &bytes[..5];
+        assert!(TraceCall::read(&mut truncated).is_err());
+    }
+
+    #[test]
+    fn pixels_size() {
+        // RGBA and UNSIGNED_BYTE.
+        assert_eq!(get_pixels_size(3, 2, RGBA, UNSIGNED_BYTE, 4), 24);
+        // RGB rows are padded to the alignment.
+        assert_eq!(get_pixels_size(3, 2, RGB, UNSIGNED_BYTE, 4), 24);
+        assert_eq!(get_pixels_size(3, 2, RGB, UNSIGNED_BYTE, 1), 18);
+        // RED and FLOAT, RGBA and HALF_FLOAT, UNSIGNED_SHORT_5_6_5.
+        assert_eq!(get_pixels_size(5, 1, RED, FLOAT, 4), 20);
+        assert_eq!(get_pixels_size(2, 2, RGBA, HALF_FLOAT, 4), 32);
+        assert_eq!(get_pixels_size(3, 1, RGB, UNSIGNED_SHORT_5_6_5, 2), 6);
+        assert_eq!(get_pixels_size(3, 1, RGBA, UNSIGNED_BYTE, 0), 12);
+    }
+
+    #[test]
+    fn output_size_follows_the_call() {
+        // A 1024 by 1024 RGBA float readback is larger than the scratch buffer.
+        let args = [0, 0, 1024, 1024, RGBA, FLOAT, 0];
+        assert_eq!(get_output_size("glReadPixels", &args, 4), 1 << 24);
+        assert_eq!(
+            get_output_size("glGetBufferSubData", &[ARRAY_BUFFER, 0, 1 << 20, 0], 4),
+            1 << 20
+        );
+        assert_eq!(
+            get_output_size(
+                "glReadPixels",
+                &[0, 0, -1i32 as u64, 4, RGBA, UNSIGNED_BYTE, 0],
+                4
+            ),
+            0
+        );
+    }
+
+    #[test]
+    fn client_arrays_are_recorded_at_draw_time() {
+        let mut recorder = new_recorder();
+        let vertices: [f32; 8] = [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0];
+        let indices: [u16; 3] = [0, 3, 1];
+        let pointer = vertices.as_ptr() as usize as u64;
+        recorder.get_payloads("glEnableVertexAttribArray", &[0]);
+        let payloads =
+            recorder.get_payloads("glVertexAttribPointer", &[0, 2, FLOAT, 0, 0, pointer]);
+        assert!(payloads.is_empty());
+        let draw = [
+            TRIANGLES,
+            3,
+            UNSIGNED_SHORT,
+            indices.as_ptr() as usize as u64,
+        ];
+        let calls = recorder.get_client_arrays("glDrawElements", &draw);
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].payloads[0].index, 5);
+        // The largest index is 3, so four vertices of two floats.
+        assert_eq!(calls[0].payloads[0].data.len(), 32);
+        let payloads = recorder.get_payloads("glDrawElements", &draw);
+        assert_eq!(payloads[0].data, [0, 0, 3, 0, 1, 0]);
+        assert!(!payloads[0].offset);
+
+        recorder.get_payloads("glBindBuffer", &[ELEMENT_ARRAY_BUFFER, 4]);
+        recorder.get_payloads("glBindBuffer", &[ARRAY_BUFFER, 5]);
+        let payloads = recorder.get_payloads("glVertexAttribPointer", &[0, 2, FLOAT, 0, 0, 8]);
+        assert!(payloads[0].offset);
+        assert!(recorder
+            .get_client_arrays("glDrawElements", &[TRIANGLES, 3, UNSIGNED_SHORT, 0])
+            .is_empty());
+        assert!(
+            recorder.get_payloads("glDrawElements", &[TRIANGLES, 3, UNSIGNED_SHORT, 0])[0].offset
+        );
+        recorder.get_payloads("glDeleteBuffers", &[1, &4u32 as *const u32 as usize as u64]);
+        assert_eq!(recorder.get_element_buffer(), 0);
+    }
+
+    #[cfg(feature = "gl-mock")]
+    #[test]
+    fn uniform_locations_are_remapped() {
+        use {crate::mock, std::ffi::CString};
+
+        mock::reset();
+        let loader = Loader::new_mock();
+        let mut replayer = Replayer::new(&MAGIC[..]).unwrap();
+        let call = |name: &str, args: Vec<u64>, result: u64, payloads: Vec<Payload>| TraceCall {
+            name: name.to_string(),
+            args,
+            result,
+            payloads,
+        };
+        let name = |s: &str| Payload {
+            index: 1,
+            offset: false,
+            data: CString::new(s).unwrap().into_bytes_with_nul(),
+        };
+        let calls = [
+            call("glCreateProgram", vec![], 40, vec![]),
+            call("glUseProgram", vec![40], 0, vec![]),
+            call("glGetUniformLocation", vec![40, 1], 9, vec![name("b")]),
+            call("glGetUniformLocation", vec![40, 1], 3, vec![name("a")]),
+            call("glUniform1i", vec![3, 1], 0, vec![]),
+            call("glUniform1i", vec![9, 2], 0, vec![]),
+        ];
+        for c in &calls {
+            assert!(replayer.replay_call(&loader, c));
+        }
+        let program = mock::get_calls_of("glUseProgram")[0].args[0];
+        assert_eq!(mock::get_current_program() as u64, program);
+        let uniforms = mock::get_calls_of("glUniform1i");
+        assert_eq!(uniforms[0].args, [1, 1]);
+        assert_eq!(uniforms[1].args, [0, 2]);
+    }
+}
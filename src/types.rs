@@ -1,9 +1,30 @@
-pub(crate) type Boolean = u8;
-pub(crate) type BitField = u32;
-pub(crate) type Enumerated = u32;
-pub(crate) type SInt = i32;
-pub(crate) type SizeI = u32;
-pub(crate) type SizeIPtr = isize;
-// pub(crate) type SShort = i16;
-pub(crate) type UInt = u32;
-// pub(crate) type UShort = u16;
+use std::os::raw::{c_char, c_void};
+
+pub type Boolean = u8;
+pub type BitField = u32;
+pub type DebugProc = Option<
+    extern "C" fn(
+        source: Enumerated,
+        message_type: Enumerated,
+        id: UInt,
+        severity: Enumerated,
+        length: SizeI,
+        message: *const c_char,
+        user_param: *const c_void,
+    ),
+>;
+pub type Enumerated = u32;
+pub type Fixed = i32;
+pub type Half = u16;
+pub type IntPtr = isize;
+pub type SByte = i8;
+pub type SInt = i32;
+pub type SInt64 = i64;
+pub type SizeI = u32;
+pub type SizeIPtr = isize;
+pub type SShort = i16;
+pub type SyncObject = *const c_void;
+pub type UByte = u8;
+pub type UInt = u32;
+pub type UInt64 = u64;
+pub type UShort = u16;
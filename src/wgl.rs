@@ -0,0 +1,182 @@
+use {
+    error::GlError,
+    std::{
+        ffi::{CStr, CString},
+        mem::{size_of, transmute_copy, zeroed},
+        os::raw::{c_char, c_int},
+        ptr::{null, null_mut},
+    },
+    winapi::{
+        shared::{
+            minwindef::{BOOL, FALSE, FLOAT, UINT, WORD},
+            windef::{HDC, HGLRC, HWND},
+        },
+        um::{
+            errhandlingapi::GetLastError,
+            wingdi::{
+                wglCreateContext, wglDeleteContext, wglGetProcAddress, wglMakeCurrent,
+                ChoosePixelFormat, SetPixelFormat, PFD_DOUBLEBUFFER, PFD_DRAW_TO_WINDOW,
+                PFD_SUPPORT_OPENGL, PFD_TYPE_RGBA, PIXELFORMATDESCRIPTOR,
+            },
+            winuser::{CreateWindowExA, DestroyWindow, GetDC, ReleaseDC, WS_POPUP},
+        },
+    },
+};
+
+pub const DRAW_TO_WINDOW_ARB: c_int = 0x2001;
+pub const ACCELERATION_ARB: c_int = 0x2003;
+pub const SUPPORT_OPENGL_ARB: c_int = 0x2010;
+pub const DOUBLE_BUFFER_ARB: c_int = 0x2011;
+pub const PIXEL_TYPE_ARB: c_int = 0x2013;
+pub const RED_BITS_ARB: c_int = 0x2015;
+pub const GREEN_BITS_ARB: c_int = 0x2017;
+pub const BLUE_BITS_ARB: c_int = 0x2019;
+pub const ALPHA_BITS_ARB: c_int = 0x201B;
+pub const DEPTH_BITS_ARB: c_int = 0x2022;
+pub const STENCIL_BITS_ARB: c_int = 0x2023;
+pub const FULL_ACCELERATION_ARB: c_int = 0x2027;
+pub const TYPE_RGBA_ARB: c_int = 0x202B;
+pub const SAMPLE_BUFFERS_ARB: c_int = 0x2041;
+pub const SAMPLES_ARB: c_int = 0x2042;
+pub const FRAMEBUFFER_SRGB_CAPABLE_ARB: c_int = 0x20A9;
+pub const CONTEXT_MAJOR_VERSION_ARB: c_int = 0x2091;
+pub const CONTEXT_MINOR_VERSION_ARB: c_int = 0x2092;
+pub const CONTEXT_FLAGS_ARB: c_int = 0x2094;
+pub const CONTEXT_DEBUG_BIT_ARB: c_int = 0x0001;
+pub const CONTEXT_FORWARD_COMPATIBLE_BIT_ARB: c_int = 0x0002;
+pub const CONTEXT_ROBUST_ACCESS_BIT_ARB: c_int = 0x0004;
+pub const CONTEXT_PROFILE_MASK_ARB: c_int = 0x9126;
+pub const CONTEXT_CORE_PROFILE_BIT_ARB: c_int = 0x0001;
+pub const CONTEXT_COMPATIBILITY_PROFILE_BIT_ARB: c_int = 0x0002;
+pub const CONTEXT_ES2_PROFILE_BIT_EXT: c_int = 0x0004;
+pub const CONTEXT_RESET_NOTIFICATION_STRATEGY_ARB: c_int = 0x8256;
+pub const NO_RESET_NOTIFICATION_ARB: c_int = 0x8261;
+pub const LOSE_CONTEXT_ON_RESET_ARB: c_int = 0x8252;
+pub const CONTEXT_OPENGL_NO_ERROR_ARB: c_int = 0x31B3;
+
+#[derive(Clone)]
+pub struct Wgl {
+    pub choose_pixel_format: Option<
+        extern "system" fn(
+            hdc: HDC,
+            attrib_i_list: *const c_int,
+            attrib_f_list: *const FLOAT,
+            max_formats: UINT,
+            formats: *mut c_int,
+            num_formats: *mut UINT,
+        ) -> BOOL,
+    >,
+    pub create_context_attribs:
+        Option<extern "system" fn(hdc: HDC, share: HGLRC, attrib_list: *const c_int) -> HGLRC>,
+    pub swap_interval: Option<extern "system" fn(interval: c_int) -> BOOL>,
+    pub get_swap_interval: Option<extern "system" fn() -> c_int>,
+    extensions: String,
+}
+
+pub fn get_function<T>(name: &str) -> Option<T> {
+    let cs = CString::new(name).unwrap();
+    let f = unsafe { wglGetProcAddress(cs.as_ptr()) } as isize;
+    // Some drivers return these instead of null for the functions they do not export.
+    if f == 0 || f == 1 || f == 2 || f == 3 || f == -1 {
+        return None;
+    }
+    Some(unsafe { transmute_function(f) })
+}
+
+unsafe fn transmute_function<T>(f: isize) -> T {
+    assert_eq!(size_of::<T>(), size_of::<isize>());
+    transmute_copy(&f)
+}
+
+impl Wgl {
+    // The extension entry points can only be fetched with a current context, and a window can only get
+    // its pixel format once, so they are fetched through a throwaway window.
+    pub fn new() -> Result<Self, GlError> {
+        let window: HWND = unsafe {
+            CreateWindowExA(
+                0,
+                b"STATIC\0".as_ptr() as *const c_char,
+                b"\0".as_ptr() as *const c_char,
+                WS_POPUP,
+                0,
+                0,
+                1,
+                1,
+                null_mut(),
+                null_mut(),
+                null_mut(),
+                null_mut(),
+            )
+        };
+        if window.is_null() {
+            return Err(GlError::DeviceContext);
+        }
+        let device = unsafe { GetDC(window) };
+        let result = Self::load(device);
+        unsafe {
+            ReleaseDC(window, device);
+            DestroyWindow(window);
+        }
+        result
+    }
+
+    fn load(device: HDC) -> Result<Self, GlError> {
+        if device.is_null() {
+            return Err(GlError::DeviceContext);
+        }
+        let mut desc: PIXELFORMATDESCRIPTOR = unsafe { zeroed() };
+        desc.nSize = size_of::<PIXELFORMATDESCRIPTOR>() as WORD;
+        desc.nVersion = 1;
+        desc.dwFlags = PFD_DRAW_TO_WINDOW | PFD_SUPPORT_OPENGL | PFD_DOUBLEBUFFER;
+        desc.iPixelType = PFD_TYPE_RGBA;
+        desc.cColorBits = 32;
+        desc.cDepthBits = 24;
+        let format = unsafe { ChoosePixelFormat(device, &desc) };
+        if format == 0 || FALSE == unsafe { SetPixelFormat(device, format, &desc) } {
+            return Err(GlError::PixelFormat);
+        }
+        let render = unsafe { wglCreateContext(device) };
+        if render.is_null() {
+            return Err(GlError::ContextCreation(Vec::new()));
+        }
+        if FALSE == unsafe { wglMakeCurrent(device, render) } {
+            unsafe { wglDeleteContext(render) };
+            return Err(GlError::MakeCurrent(unsafe { GetLastError() } as i32));
+        }
+        let get_extensions_string: Option<extern "system" fn(hdc: HDC) -> *const c_char> =
+            get_function("wglGetExtensionsStringARB");
+        let extensions = get_extensions_string
+            .map(|f| f(device))
+            .filter(|e| !e.is_null())
+            .map(|e| unsafe { CStr::from_ptr(e) }.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let wgl = Self {
+            choose_pixel_format: get_function("wglChoosePixelFormatARB"),
+            create_context_attribs: get_function("wglCreateContextAttribsARB"),
+            swap_interval: get_function("wglSwapIntervalEXT"),
+            get_swap_interval: get_function("wglGetSwapIntervalEXT"),
+            extensions,
+        };
+        unsafe {
+            wglMakeCurrent(null_mut(), null_mut());
+            wglDeleteContext(render);
+        }
+        Ok(wgl)
+    }
+
+    pub fn has_extension(&self, name: &str) -> bool {
+        self.extensions.split_whitespace().any(|e| e == name)
+    }
+
+    pub fn find_pixel_format(&self, device: HDC, attribs: &[c_int]) -> Option<c_int> {
+        let choose = self.choose_pixel_format?;
+        let mut format = 0;
+        let mut count = 0;
+        if FALSE == choose(device, attribs.as_ptr(), null(), 1, &mut format, &mut count)
+            || count == 0
+        {
+            return None;
+        }
+        Some(format)
+    }
+}